@@ -0,0 +1,243 @@
+use crate::http_client::network_client;
+use anyhow::{anyhow, bail, Context, Result};
+use bytes::Bytes;
+use http_body_util::Full;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::{fs, io};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CassetteMode {
+    /// Performs the requests and saves every exchange
+    Record,
+    /// Serves the saved exchanges, without network access
+    Replay,
+}
+
+/// A recorded HTTP request and its response. Request headers aren't kept, they may contain the token.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Exchange {
+    pub method: String,
+    pub url: String,
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl Exchange {
+    /// Sends `request` over the network
+    pub async fn perform(client: &reqwest::Client, request: reqwest::Request) -> Result<Self> {
+        let method = request.method().to_string();
+        let url = request.url().to_string();
+        let response = client.execute(request).await?;
+
+        Ok(Self {
+            method,
+            url,
+            status: response.status().as_u16(),
+            headers: response
+                .headers()
+                .iter()
+                .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+                .collect(),
+            body: response.text().await?,
+        })
+    }
+
+    pub fn into_response(self) -> Result<http::Response<Full<Bytes>>> {
+        let mut response = http::Response::builder().status(self.status);
+        for (name, value) in &self.headers {
+            response = response.header(name, value);
+        }
+        Ok(response.body(Full::new(Bytes::from(self.body)))?)
+    }
+}
+
+/// A directory of HTTP exchanges, one JSON file per exchange, named in request order. When the same
+/// request is made several times, the replay serves the recorded responses in order, and the last one
+/// after that.
+#[derive(Debug, Clone)]
+pub struct Cassette {
+    mode: CassetteMode,
+    dir: PathBuf,
+    client: reqwest::Client,
+    state: Arc<Mutex<CassetteState>>,
+}
+
+#[derive(Debug, Default)]
+struct CassetteState {
+    /// The directory is cleared or loaded on the first request
+    loaded: bool,
+    recorded: usize,
+    replay: HashMap<(String, String), VecDeque<Exchange>>,
+    replayed: HashMap<(String, String), Exchange>,
+}
+
+impl CassetteState {
+    fn push_replay(&mut self, exchange: Exchange) {
+        self.replay
+            .entry((exchange.method.clone(), exchange.url.clone()))
+            .or_default()
+            .push_back(exchange);
+    }
+}
+
+impl Cassette {
+    pub fn new(mode: CassetteMode, dir: impl Into<PathBuf>) -> Self {
+        Self {
+            mode,
+            dir: dir.into(),
+            client: network_client(),
+            state: Arc::default(),
+        }
+    }
+
+    /// Replays `exchanges` from memory, as if they were recorded in this order. Reported as the directory
+    /// `<memory>`.
+    pub fn replaying(exchanges: impl IntoIterator<Item = Exchange>) -> Self {
+        let cassette = Self::new(CassetteMode::Replay, "<memory>");
+        {
+            let mut state = cassette.state.lock().unwrap();
+            for exchange in exchanges {
+                state.push_replay(exchange);
+            }
+            state.loaded = true;
+        }
+        cassette
+    }
+
+    /// `HTTP_CASSETTE_RECORD=dir` records into `dir`, `HTTP_CASSETTE_REPLAY=dir` replays from `dir`
+    pub fn from_env() -> Option<Self> {
+        if let Ok(dir) = std::env::var("HTTP_CASSETTE_RECORD") {
+            return Some(Self::new(CassetteMode::Record, dir));
+        }
+
+        std::env::var("HTTP_CASSETTE_REPLAY").ok().map(|dir| Self::new(CassetteMode::Replay, dir))
+    }
+
+    pub fn mode(&self) -> CassetteMode {
+        self.mode
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Performs and records `request`, or replays its recorded response
+    pub async fn send(&self, request: reqwest::Request) -> Result<Exchange> {
+        let method = request.method().to_string();
+        let url = request.url().to_string();
+        self.load()?;
+
+        match self.mode {
+            CassetteMode::Replay => {
+                let mut state = self.state.lock().unwrap();
+                let key = (method, url);
+                if let Some(exchange) = state.replay.get_mut(&key).and_then(VecDeque::pop_front) {
+                    state.replayed.insert(key, exchange.clone());
+                    return Ok(exchange);
+                }
+                state
+                    .replayed
+                    .get(&key)
+                    .cloned()
+                    .ok_or_else(|| anyhow!("no recorded response for {} {} in {}", key.0, key.1, self.dir.display()))
+            }
+            CassetteMode::Record => {
+                let exchange = Exchange::perform(&self.client, request).await?;
+                self.save(&exchange)?;
+                Ok(exchange)
+            }
+        }
+    }
+
+    /// GETs `url` and returns the body of a successful response
+    pub async fn get_text(&self, url: &str) -> Result<String> {
+        let exchange = self.send(self.client.get(url).build()?).await?;
+        if !(200..300).contains(&exchange.status) {
+            bail!("GET {url} returned {}", exchange.status);
+        }
+        Ok(exchange.body)
+    }
+
+    fn load(&self) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        if state.loaded {
+            return Ok(());
+        }
+
+        match self.mode {
+            CassetteMode::Record => {
+                fs::create_dir_all(&self.dir).with_context(|| format!("creating {}", self.dir.display()))?;
+                // Only a previous recording is replaced, other files in the directory are left alone
+                for path in exchange_files(&self.dir)?.into_iter().filter(|path| is_recorded_exchange(path)) {
+                    fs::remove_file(&path).with_context(|| format!("removing {}", path.display()))?;
+                }
+            }
+            CassetteMode::Replay => {
+                for path in exchange_files(&self.dir)? {
+                    let body = fs::read_to_string(&path)?;
+                    let exchange: Exchange =
+                        serde_json::from_str(&body).with_context(|| format!("can't parse {}", path.display()))?;
+                    state.push_replay(exchange);
+                }
+            }
+        }
+
+        state.loaded = true;
+        Ok(())
+    }
+
+    fn save(&self, exchange: &Exchange) -> Result<()> {
+        let seq = {
+            let mut state = self.state.lock().unwrap();
+            state.recorded += 1;
+            state.recorded
+        };
+
+        let slug: String = exchange
+            .url
+            .trim_start_matches("https://")
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '.' { c } else { '_' })
+            .take(100)
+            .collect();
+        let path = self.dir.join(format!("{seq:05}-{}-{slug}.json", exchange.method.to_lowercase()));
+        fs::write(&path, serde_json::to_string_pretty(exchange)? + "\n")
+            .with_context(|| format!("writing {}", path.display()))
+    }
+}
+
+/// Whether `path` is named like the files `save` writes, `{seq:05}-{method}-{slug}.json`
+fn is_recorded_exchange(path: &Path) -> bool {
+    let Some(stem) = path.file_stem().and_then(|stem| stem.to_str()) else {
+        return false;
+    };
+    let mut parts = stem.splitn(3, '-');
+    let (Some(seq), Some(method), Some(_)) = (parts.next(), parts.next(), parts.next()) else {
+        return false;
+    };
+
+    seq.len() == 5 && seq.bytes().all(|b| b.is_ascii_digit()) && !method.is_empty() && method.bytes().all(|b| b.is_ascii_lowercase())
+}
+
+/// The exchange files of a cassette, in request order
+fn exchange_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).with_context(|| format!("reading {}", dir.display())),
+    };
+
+    let mut files = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "json") {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
@@ -1,10 +1,20 @@
-use crate::version_manager::VersionManager;
-use chrono::{Duration, NaiveDate, Utc};
+use crate::channel_manifest::{ChannelManifests, RustupChannel};
+use crate::edition::Edition;
+use crate::release_catalog::{ReleaseCatalog, ReleaseEntry};
+use crate::release_notes::{ApiKind, Block, Item, LinkDefinition, ReleaseNotes, SectionKind};
+use crate::version_manager::{ReleaseDate, VersionManager};
+use chrono::NaiveDate;
 use itertools::Itertools;
 use octocrab::models::issues::Issue;
 use octocrab::models::IssueId;
 use semver::Version;
-use std::collections::{HashMap, HashSet};
+use regex::{Captures, Regex};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::LazyLock;
+
+/// `[text]`, optionally followed by `[label]`, `[]` or the opening parenthesis of an inline link
+static LINK_REFERENCE_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\[([^\[\]]+)\](\[[^\[\]]*\]|\()?").unwrap());
 
 #[derive(Debug)]
 pub struct ChangelogGenerator {
@@ -16,22 +26,53 @@ impl ChangelogGenerator {
         Self { version_manager }
     }
 
-    pub fn generate_released_version_content(&self, version: &Version, changelog: &str, release_date: &NaiveDate) -> String {
-        let mut trimmed = changelog.trim().to_string();
-        if trimmed.starts_with('-') {
-            trimmed = format!("Changes\n-------\n{trimmed}");
-        }
+    /// `patch_releases` are the follow-up releases of a `x.y.0`, listed in its hint block
+    pub fn generate_released_version_content(&self, version: &Version, notes: &ReleaseNotes, release_date: &NaiveDate,
+                                             patch_releases: &[&ReleaseEntry]) -> String {
+        let changes = Self::render_release_notes(notes);
 
-        let dates = self.version_manager.calculate_release_date(*release_date - Duration::days(1), 1);
-        let version_branch_info_str = if version.patch == 0 {
+        let dates = self.version_manager.dates_for_version(version);
+        let mut version_branch_info_str = if version.patch == 0 {
             format!(
                 "- Branched from master on: _{branch_date}_",
                 branch_date = dates.branch_date.format("%-d %B, %C%y")
             )
         } else {
-            "- This is a patch release".to_string()
+            format!(
+                "- This is a patch release, part of the [{major}.{minor} series](/series/{major}.{minor}/)",
+                major = version.major,
+                minor = version.minor,
+            )
         };
 
+        if !patch_releases.is_empty() {
+            version_branch_info_str.push_str(&format!(
+                "\n- Followed by patch releases, see the [{major}.{minor} series](/series/{major}.{minor}/):",
+                major = version.major,
+                minor = version.minor,
+            ));
+            for patch in patch_releases {
+                version_branch_info_str.push_str(&format!("\n  - [{v}](/docs/{v})", v = patch.version));
+                if let Some(date) = patch.release_date {
+                    version_branch_info_str.push_str(&format!(", _{}_", date.format("%-d %B, %C%y")));
+                }
+                if let Some(summary) = patch.notes.summary() {
+                    let more = patch.notes.items().count().saturating_sub(1);
+                    version_branch_info_str.push_str(&format!(": {summary}"));
+                    if more > 0 {
+                        version_branch_info_str.push_str(&format!(" (and {})", pluralizer::pluralize("more fix", more as isize, true)));
+                    }
+                }
+            }
+        }
+
+        if let Some(edition) = self.version_manager.editions().stabilized_by(version) {
+            version_branch_info_str.push_str(&format!(
+                "\n- 🎉 Stabilizes the [{edition} edition](/editions/#{anchor})",
+                anchor = edition.anchor(),
+            ));
+        }
+
         format!(
             "---
 weight: {weight}
@@ -46,7 +87,7 @@ weight: {weight}
 {version_branch_info_str}
 {{{{% /hint %}}}}
 
-{trimmed}
+{changes}
 ",
             weight = self.version_manager.determine_weight(version),
             release_date = release_date.format("%-d %B, %C%y"),
@@ -54,6 +95,314 @@ weight: {weight}
         )
     }
 
+    /// All releases of a `x.y` series on one page, newest first
+    pub fn generate_series_content(&self, series: &[&ReleaseEntry]) -> String {
+        let Some(first) = series.first() else {
+            return String::new();
+        };
+        let name = format!("{}.{}", first.version.major, first.version.minor);
+
+        let mut content = format!(
+            "---
+title: {name} series
+type: docs
+---
+
+# Rust {name} series
+
+{{{{% hint info %}}}}
+"
+        );
+        for entry in series {
+            content.push_str(&format!("- [{v}](/docs/{v})", v = entry.version));
+            if let Some(date) = entry.release_date {
+                content.push_str(&format!(": _{}_", date.format("%-d %B, %C%y")));
+            }
+            content.push('\n');
+        }
+        content.push_str("{{% /hint %}}\n");
+
+        // Link labels are shared by the whole page, a label which an earlier version defined with another URL
+        // is renamed
+        let mut links: Vec<LinkDefinition> = Vec::new();
+        for entry in series.iter().rev() {
+            let mut renamed = Vec::new();
+            for link in &entry.notes.links {
+                let defined = links.iter().find(|defined| defined.label.eq_ignore_ascii_case(&link.label));
+                match defined {
+                    Some(defined) if defined.url == link.url => {}
+                    Some(_) => {
+                        let label = format!("{}-{}", link.label, entry.version);
+                        renamed.push((link.label.as_str(), label.clone()));
+                        links.push(LinkDefinition { label, url: link.url.clone() });
+                    }
+                    None => links.push(link.clone()),
+                }
+            }
+
+            content.push_str(&format!("\n## {}\n", entry.version));
+            for section in &entry.notes.sections {
+                let title = section.title.as_deref().unwrap_or("Changes");
+                let body = renamed.iter().fold(section.body(), |body, (from, to)| rename_link_label(&body, from, to));
+                content.push_str(&format!("\n### {title}\n\n{body}\n"));
+            }
+        }
+
+        Self::push_link_definitions(&mut content, links.iter().collect());
+        content
+    }
+
+    /// The cycle events which aren't over yet, as a list with every line starting with `indent`
+    fn render_upcoming_events(&self, release_date: &ReleaseDate, indent: &str) -> String {
+        release_date
+            .events
+            .iter()
+            .filter(|event| event.end_date.unwrap_or(event.date) >= self.version_manager.today())
+            .map(|event| {
+                let dates = match event.end_date {
+                    Some(end_date) => format!("_{}_ to _{}_", event.date.format("%-d %B, %C%y"), end_date.format("%-d %B, %C%y")),
+                    None => format!("_{}_", event.date.format("%-d %B, %C%y")),
+                };
+                format!("{indent}- {}: {dates}\n", event.name)
+            })
+            .collect()
+    }
+
+    fn render_channels(channels: &ChannelManifests) -> String {
+        let mut content = "
+### Toolchain Channels
+
+| Channel | Version | Commit | Published |
+|---------|---------|--------|-----------|
+"
+        .to_string();
+
+        for manifest in [&channels.stable, &channels.beta, &channels.nightly] {
+            let mut version = manifest.version.to_string();
+            if manifest.channel == RustupChannel::Beta && channels.beta_pending_bump() {
+                version.push_str(&format!(
+                    " _(not yet bumped to {}.{}.0)_",
+                    channels.stable.version.major,
+                    channels.stable.version.minor + 1
+                ));
+            }
+            let commit = manifest
+                .commit_hash
+                .as_ref()
+                .map(|hash| format!("[`{}`](https://github.com/rust-lang/rust/commit/{hash})", &hash[..hash.len().min(9)]))
+                .unwrap_or_default();
+
+            content.push_str(&format!(
+                "| {channel} | {version} | {commit} | {date} |\n",
+                channel = manifest.channel,
+                date = manifest.date.format("%-d %B, %C%y"),
+            ));
+        }
+
+        content
+    }
+
+    fn render_release_notes(notes: &ReleaseNotes) -> String {
+        let mut notes = notes.clone();
+        if let Some(section) = notes.sections.first_mut() {
+            if section.title.is_none() && matches!(section.blocks.first(), Some(Block::Item(_))) {
+                section.title = Some("Changes".to_string());
+            }
+        }
+
+        notes.to_markdown().trim().to_string()
+    }
+
+    pub fn generate_stabilized_apis_content(&self, catalog: &ReleaseCatalog) -> String {
+        struct Row {
+            kind: ApiKind,
+            docs_url: Option<String>,
+            stable: Option<Version>,
+            const_stable: Option<Version>,
+        }
+
+        let mut rows: BTreeMap<(String, String), Row> = BTreeMap::new();
+        for (entry, _) in catalog.dated() {
+            let version = &entry.version;
+            for api in entry.notes.stabilized_apis() {
+                let row = rows.entry((api.path.to_lowercase(), api.path.clone())).or_insert_with(|| Row {
+                    kind: api.kind,
+                    docs_url: None,
+                    stable: None,
+                    const_stable: None,
+                });
+                let since = if api.is_const { &mut row.const_stable } else { &mut row.stable };
+                since.get_or_insert_with(|| version.clone());
+                if row.docs_url.is_none() {
+                    row.docs_url = api.docs_url;
+                }
+            }
+        }
+
+        let version_link = |version: &Option<Version>| {
+            version.as_ref().map(|v| format!("[{v}](/docs/{v})")).unwrap_or_default()
+        };
+
+        let mut content = "---
+title: Stabilized APIs
+type: docs
+---
+
+# Stabilized APIs
+
+Every API listed in the \"Stabilized APIs\" section of the release notes, with the version it became stable
+and the version it became usable in const contexts.
+
+| API | Kind | Stable since | Const since |
+|-----|------|--------------|-------------|
+"
+        .to_string();
+
+        for ((_, path), row) in rows {
+            let api = format!("`{}`", path.replace('|', "\\|"));
+            let api = match row.docs_url {
+                Some(url) => format!("[{api}]({url})"),
+                None => api,
+            };
+            content.push_str(&format!(
+                "| {api} | {kind} | {stable} | {const_stable} |\n",
+                kind = row.kind.name(),
+                stable = version_link(&row.stable),
+                const_stable = version_link(&row.const_stable),
+            ));
+        }
+
+        content
+    }
+
+    pub fn generate_compatibility_notes_content(&self, catalog: &ReleaseCatalog) -> String {
+        let mut content = "---
+title: Compatibility Notes
+type: docs
+---
+
+# Compatibility Notes
+
+The \"Compatibility Notes\" of every release, newest first.
+"
+        .to_string();
+
+        let mut links = Vec::new();
+        for (ReleaseEntry { version, notes, .. }, _) in catalog.dated().rev() {
+            let items: Vec<_> = notes.section(SectionKind::CompatibilityNotes).into_iter().flat_map(|s| s.items()).collect();
+            if items.is_empty() {
+                continue;
+            }
+
+            content.push_str(&format!("\n## [{version}](/docs/{version})\n\n"));
+            for item in &items {
+                content.push_str(&format!("{item}\n"));
+            }
+            links.extend(notes.links_used_by(&items));
+        }
+
+        Self::push_link_definitions(&mut content, links);
+        content
+    }
+
+    /// The edition-related release notes items of every release, grouped by the edition they mention,
+    /// newest first
+    pub fn generate_editions_content(&self, catalog: &ReleaseCatalog) -> String {
+        let editions = self.version_manager.editions();
+        let mut content = "---
+title: Editions
+type: docs
+---
+
+# Editions
+
+The release notes items mentioning editions, newest first.
+"
+        .to_string();
+
+        let mut links = Vec::new();
+        let mut render_items = |edition: Option<&Edition>| {
+            let mut content = String::new();
+            for (ReleaseEntry { version, notes, .. }, _) in catalog.dated().rev() {
+                let items: Vec<_> = notes
+                    .items()
+                    .filter(|item| match (editions.mentioned_by(item), edition) {
+                        (Some(mentioned), Some(edition)) => mentioned.contains(edition),
+                        (Some(mentioned), None) => mentioned.is_empty(),
+                        (None, _) => false,
+                    })
+                    .collect();
+                if items.is_empty() {
+                    continue;
+                }
+
+                content.push_str(&format!("\n### [{version}](/docs/{version})\n\n"));
+                for item in &items {
+                    content.push_str(&format!("{item}\n"));
+                }
+                links.extend(notes.links_used_by(&items));
+            }
+            content
+        };
+
+        for edition in editions.iter().rev() {
+            content.push_str(&format!(
+                "\n## {edition}\n\n{{{{% hint info %}}}}\nStable since [{v}](/docs/{v})\n{{{{% /hint %}}}}\n",
+                v = edition.version,
+            ));
+            content.push_str(&render_items(Some(&edition)));
+        }
+
+        let other = render_items(None);
+        if !other.is_empty() {
+            content.push_str(&format!("\n## Other edition changes\n{other}"));
+        }
+
+        Self::push_link_definitions(&mut content, links);
+        content
+    }
+
+    /// A markdown checklist of the compatibility notes and Cargo changes to go through when upgrading
+    /// the toolchain from `from` to `to`, oldest first.
+    pub fn generate_upgrade_checklist(&self, catalog: &ReleaseCatalog, from: &Version, to: &Version) -> String {
+        let mut content = format!("# Upgrading from Rust {from} to {to}\n");
+
+        let mut links = Vec::new();
+        for (ReleaseEntry { version, notes, .. }, _) in catalog.dated().filter(|(entry, _)| entry.version > *from && entry.version <= *to) {
+            let mut version_content = String::new();
+            for kind in [SectionKind::CompatibilityNotes, SectionKind::Cargo] {
+                let items: Vec<&Item> = notes.section(kind).into_iter().flat_map(|s| s.items()).collect();
+                if items.is_empty() {
+                    continue;
+                }
+
+                version_content.push_str(&format!("\n### {}\n\n", kind.title().unwrap_or_default()));
+                for item in &items {
+                    version_content.push_str(&format!("- [ ] {}\n", item.text));
+                }
+                links.extend(notes.links_used_by(&items));
+            }
+
+            if !version_content.is_empty() {
+                content.push_str(&format!("\n## {version}\n{version_content}"));
+            }
+        }
+
+        Self::push_link_definitions(&mut content, links);
+        content
+    }
+
+    fn push_link_definitions(content: &mut String, links: Vec<&LinkDefinition>) {
+        if links.is_empty() {
+            return;
+        }
+
+        content.push('\n');
+        for link in links.into_iter().unique_by(|link| link.label.to_lowercase()) {
+            content.push_str(&format!("[{}]: {}\n", link.label, link.url));
+        }
+    }
+
     pub fn generate_unreleased_version_content(&self, unreleased_version: &Version, _milestone_id: i64, 
                                           stable_version: &Version, issues: &[Issue]) -> String {
         let release_name = if unreleased_version.minor == stable_version.minor + 2 {
@@ -64,11 +413,8 @@ weight: {weight}
             ""
         };
 
-        let release_date = self.version_manager.calculate_release_date(
-            Utc::now().date_naive(),
-            (unreleased_version.minor - stable_version.minor) as u32,
-        );
-        let already_branched = Utc::now().naive_utc().date() > release_date.branch_date;
+        let release_date = self.version_manager.dates_for_version(unreleased_version);
+        let already_branched = self.version_manager.today() > release_date.branch_date;
 
         let mut changelog = format!(
             "---
@@ -84,7 +430,7 @@ weight: {weight}
 
 - Will be stable on: _{stable_date}_
 - {branch_pfx} from master on: _{branch_date}_
-{{{{% /hint %}}}}
+{events}{{{{% /hint %}}}}
 
 ",
             weight = self.version_manager.determine_weight(unreleased_version),
@@ -92,12 +438,13 @@ weight: {weight}
             stable_date = release_date.release_date.format("%-d %B, %C%y"),
             branch_pfx = if already_branched { "Branched" } else { "Will branch" },
             branch_date = release_date.branch_date.format("%-d %B, %C%y"),
+            events = self.render_upcoming_events(&release_date, ""),
         );
 
         for (issue, days_ago) in issues.iter()
             .filter_map(|issue| {
                 issue.closed_at.map(|closed_at| {
-                    (issue, (Utc::now().naive_utc().date() - closed_at.naive_utc().date()).num_days())
+                    (issue, (self.version_manager.today() - closed_at.naive_utc().date()).num_days())
                 })
             })
             .sorted_by_key(|(_, days_ago)| *days_ago)
@@ -116,7 +463,8 @@ weight: {weight}
     }
 
     pub fn generate_index_content(&self, stable_version: &Version, beta_version: &Version, nightly_version: &Version,
-                             unreleased_versions: &HashSet<&Version>, stabilization_prs: HashMap<IssueId, Issue>) -> String {
+                             unreleased_versions: &HashSet<&Version>, channels: Option<&ChannelManifests>,
+                             stabilization_prs: HashMap<IssueId, Issue>) -> String {
         let mut index = format!(
             "---
 title: Rust Versions
@@ -130,27 +478,41 @@ type: docs
         );
 
         if unreleased_versions.contains(beta_version) {
-            let release_date = self.version_manager.calculate_release_date(Utc::now().date_naive(), 1);
-            let days_left = (release_date.release_date - Utc::now().naive_utc().date()).num_days();
+            let release_date = self.version_manager.dates_for_version(beta_version);
+            let days_left = (release_date.release_date - self.version_manager.today()).num_days();
             let days_left_text = pluralizer::pluralize("day", days_left as isize, true);
 
             index.push_str(&format!(
                 "- Beta: [{beta_version}](/docs/{beta_version}) ({}, {days_left_text} left)\n",
                 release_date.release_date.format("%-d %B, %C%y"),
             ));
+            index.push_str(&self.render_upcoming_events(&release_date, "  "));
         }
 
         if unreleased_versions.contains(nightly_version) {
-            let release_date = self.version_manager.calculate_release_date(Utc::now().date_naive(), 2);
-            let days_left = (release_date.release_date - Utc::now().naive_utc().date()).num_days();
+            let release_date = self.version_manager.dates_for_version(nightly_version);
+            let days_left = (release_date.release_date - self.version_manager.today()).num_days();
             let days_left_text = pluralizer::pluralize("day", days_left as isize, true);
 
             index.push_str(&format!(
                 "- Nightly: [{nightly_version}](/docs/{nightly_version}) ({}, {days_left_text} left)\n",
                 release_date.release_date.format("%-d %B, %C%y")
             ));
+            index.push_str(&self.render_upcoming_events(&release_date, "  "));
         }
 
+        if let Some(channels) = channels {
+            index.push_str(&Self::render_channels(channels));
+        }
+
+        index.push_str("
+## Reference
+
+- [Stabilized APIs](/stabilized-apis/)
+- [Compatibility Notes](/compatibility-notes/)
+- [Editions](/editions/)
+");
+
         index.push_str("
 
 ## Ongoing Stabilization PRs
@@ -166,7 +528,7 @@ type: docs
             ..
         } in stabilization_prs.into_values().sorted_by_key(|l| l.created_at).rev()
         {
-            let days_ago = (Utc::now() - created_at).num_days();
+            let days_ago = (self.version_manager.clock().now() - created_at).num_days();
             let days_ago_text = pluralizer::pluralize("day", days_ago as isize, true);
             let mut line = "".to_string();
             let title = title.replace('"', "\\\"");
@@ -197,9 +559,99 @@ type: docs
 - Generated at <span class=\"utc-timestamp\" data-utc=\"{}\">...</span>
 
 ",
-            Utc::now().to_rfc3339()
+            self.version_manager.clock().now().to_rfc3339()
         ));
 
         index
     }
-}
\ No newline at end of file
+
+    /// An iCalendar feed with the release of every past version, and the release and beta branch dates of
+    /// the `upcoming` versions. Events link to the version pages below `site_url`.
+    pub fn generate_calendar(&self, catalog: &ReleaseCatalog, upcoming: &[Version], site_url: &str) -> String {
+        let today = self.version_manager.today();
+        let stamp = self.version_manager.clock().now().format("%Y%m%dT%H%M%SZ").to_string();
+        let site_url = site_url.trim_end_matches('/');
+
+        let mut events = Vec::new();
+        for entry in catalog.released(today) {
+            events.push((entry.release_date.unwrap(), "release", format!("Rust {} released", entry.version), &entry.version));
+        }
+        for version in upcoming.iter().filter(|v| !catalog.is_released(v, today)) {
+            let dates = self.version_manager.dates_for_version(version);
+            events.push((dates.branch_date, "beta", format!("Rust {version} branches to beta"), version));
+            events.push((dates.release_date, "release", format!("Rust {version} release"), version));
+        }
+
+        let mut calendar = [
+            "BEGIN:VCALENDAR",
+            "VERSION:2.0",
+            "PRODID:-//releases.rs//Rust release train//EN",
+            "CALSCALE:GREGORIAN",
+            "X-WR-CALNAME:Rust releases",
+        ]
+        .map(ics_line)
+        .concat();
+
+        for (date, kind, summary, version) in events {
+            let url = format!("{site_url}/docs/{version}/");
+            for line in [
+                "BEGIN:VEVENT".to_string(),
+                format!("UID:{kind}-{version}@releases.rs"),
+                format!("DTSTAMP:{stamp}"),
+                format!("DTSTART;VALUE=DATE:{}", date.format("%Y%m%d")),
+                format!("DTEND;VALUE=DATE:{}", date.succ_opt().unwrap().format("%Y%m%d")),
+                format!("SUMMARY:{}", ics_escape(&summary)),
+                format!("DESCRIPTION:{}", ics_escape(&url)),
+                format!("URL:{url}"),
+                "TRANSP:TRANSPARENT".to_string(),
+                "END:VEVENT".to_string(),
+            ] {
+                calendar.push_str(&ics_line(&line));
+            }
+        }
+
+        calendar.push_str(&ics_line("END:VCALENDAR"));
+        calendar
+    }
+}
+
+fn ics_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace(';', "\\;").replace(',', "\\,").replace('\n', "\\n")
+}
+
+/// A content line, folded after 75 octets and terminated by CRLF as RFC 5545 requires
+fn ics_line(line: &str) -> String {
+    let mut folded = String::new();
+    let mut len = 0;
+    for c in line.chars() {
+        if len + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            len = 1;
+        }
+        folded.push(c);
+        len += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+/// Points the references to the link label `from` in `text` at `to`, inline links are left alone
+fn rename_link_label(text: &str, from: &str, to: &str) -> String {
+    LINK_REFERENCE_RE
+        .replace_all(text, |caps: &Captures| {
+            let label = match caps.get(2).map(|m| m.as_str()) {
+                // An inline link, or a full reference to another label
+                Some(target) if target.starts_with('(') => return caps[0].to_string(),
+                Some(target) if target.len() > 2 => &target[1..target.len() - 1],
+                // A collapsed or shortcut reference
+                _ => &caps[1],
+            };
+
+            if label.eq_ignore_ascii_case(from) {
+                format!("[{}][{to}]", &caps[1])
+            } else {
+                caps[0].to_string()
+            }
+        })
+        .into_owned()
+}
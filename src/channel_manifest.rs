@@ -0,0 +1,158 @@
+use crate::http_client::HttpClient;
+use anyhow::{bail, Context, Result};
+use chrono::NaiveDate;
+use semver::Version;
+use std::fmt;
+use std::path::PathBuf;
+
+pub const DEFAULT_MANIFESTS_URL: &str = "https://static.rust-lang.org/dist";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RustupChannel {
+    Stable,
+    Beta,
+    Nightly,
+}
+
+impl RustupChannel {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Stable => "stable",
+            Self::Beta => "beta",
+            Self::Nightly => "nightly",
+        }
+    }
+}
+
+impl fmt::Display for RustupChannel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// The `rust` package of a `channel-rust-{channel}.toml` manifest
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChannelManifest {
+    pub channel: RustupChannel,
+    /// E.g. `1.80.0`, `1.81.0-beta.3` or `1.82.0-nightly`
+    pub version: Version,
+    /// The date the manifest was published
+    pub date: NaiveDate,
+    pub commit_hash: Option<String>,
+}
+
+impl ChannelManifest {
+    /// Only reads the few keys we need instead of the whole (multi-megabyte) manifest:
+    /// the top-level `date` and `version`/`git_commit_hash` of `[pkg.rust]`.
+    pub fn parse(channel: RustupChannel, body: &str) -> Result<Self> {
+        let mut table = "";
+        let mut date = None;
+        let mut version = None;
+        let mut commit_hash = None;
+
+        for line in body.lines().map(str::trim) {
+            if line.starts_with('[') {
+                table = line.trim_matches(|c| c == '[' || c == ']');
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let value = value.trim().trim_matches('"');
+
+            match (table, key.trim()) {
+                ("", "date") => date = Some(value.parse::<NaiveDate>().context("invalid manifest date")?),
+                ("pkg.rust", "version") => version = Some(value.to_string()),
+                ("pkg.rust", "git_commit_hash") => commit_hash = Some(value.to_string()),
+                _ => {}
+            }
+        }
+
+        let (Some(date), Some(version)) = (date, version) else {
+            bail!("{channel} manifest has no date or rust version");
+        };
+        // `1.80.0 (051478957 2024-07-21)`
+        let version = version.split_whitespace().next().unwrap_or_default();
+
+        Ok(Self {
+            channel,
+            version: Version::parse(version).with_context(|| format!("invalid {channel} version '{version}'"))?,
+            date,
+            commit_hash,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChannelManifests {
+    pub stable: ChannelManifest,
+    pub beta: ChannelManifest,
+    pub nightly: ChannelManifest,
+}
+
+impl ChannelManifests {
+    /// On release day the new stable can be out before beta is bumped, so beta still has the version of
+    /// the new stable
+    pub fn beta_pending_bump(&self) -> bool {
+        (self.beta.version.major, self.beta.version.minor) <= (self.stable.version.major, self.stable.version.minor)
+    }
+
+    /// The stable, beta and nightly versions as the site's pages name them, e.g. `1.80.1`, `1.81.0` and `1.82.0`.
+    /// A beta pending its bump is already counted as the next version.
+    pub fn current_versions(&self) -> (Version, Version, Version) {
+        let stable = Version::new(self.stable.version.major, self.stable.version.minor, self.stable.version.patch);
+        let beta = if self.beta_pending_bump() {
+            Version::new(stable.major, stable.minor + 1, 0)
+        } else {
+            Version::new(self.beta.version.major, self.beta.version.minor, 0)
+        };
+        let nightly = Version::new(self.nightly.version.major, self.nightly.version.minor, 0);
+
+        (stable, beta, nightly)
+    }
+}
+
+/// Where to read the `channel-rust-*.toml` manifests from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChannelManifestSource {
+    /// Base URL, e.g. `https://static.rust-lang.org/dist`
+    Url(String),
+    Directory(PathBuf),
+}
+
+impl ChannelManifestSource {
+    /// `RUST_CHANNEL_MANIFESTS` can point to a local directory or another base URL
+    pub fn from_env() -> Self {
+        match std::env::var("RUST_CHANNEL_MANIFESTS") {
+            Ok(location) if location.starts_with("http://") || location.starts_with("https://") => Self::Url(location),
+            Ok(location) => Self::Directory(location.into()),
+            Err(_) => Self::Url(DEFAULT_MANIFESTS_URL.to_string()),
+        }
+    }
+
+    /// Reads the manifest of `channel`, fetching a URL with `client`, i.e. through its cache or cassette
+    pub async fn fetch_with(&self, client: &HttpClient, channel: RustupChannel) -> Result<ChannelManifest> {
+        let file_name = format!("channel-rust-{channel}.toml");
+        let body = match self {
+            Self::Url(base) => client.get_text(&format!("{}/{file_name}", base.trim_end_matches('/'))).await?,
+            Self::Directory(dir) => {
+                let path = dir.join(&file_name);
+                std::fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()))?
+            }
+        };
+
+        ChannelManifest::parse(channel, &body)
+    }
+
+    pub async fn fetch_all(&self) -> Result<ChannelManifests> {
+        self.fetch_all_with(&HttpClient::default()).await
+    }
+
+    pub async fn fetch_all_with(&self, client: &HttpClient) -> Result<ChannelManifests> {
+        Ok(ChannelManifests {
+            stable: self.fetch_with(client, RustupChannel::Stable).await?,
+            beta: self.fetch_with(client, RustupChannel::Beta).await?,
+            nightly: self.fetch_with(client, RustupChannel::Nightly).await?,
+        })
+    }
+}
@@ -0,0 +1,41 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+
+/// The source of "now" for everything date-dependent in the generated pages
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Clock {
+    #[default]
+    System,
+    /// Always returns the same instant, for reproducible output
+    Fixed(DateTime<Utc>),
+}
+
+impl Clock {
+    /// A fixed clock when `SOURCE_DATE_EPOCH` (seconds since the unix epoch) is set, the system clock otherwise
+    pub fn from_env() -> Result<Self> {
+        let Ok(epoch) = std::env::var("SOURCE_DATE_EPOCH") else {
+            return Ok(Self::System);
+        };
+
+        let seconds = epoch.trim().parse().context("SOURCE_DATE_EPOCH is not a number")?;
+        let now = DateTime::from_timestamp(seconds, 0).context("SOURCE_DATE_EPOCH is out of range")?;
+
+        Ok(Self::Fixed(now))
+    }
+
+    /// A fixed clock at midnight UTC of `date`
+    pub fn fixed_date(date: NaiveDate) -> Self {
+        Self::Fixed(date.and_hms_opt(0, 0, 0).unwrap().and_utc())
+    }
+
+    pub fn now(&self) -> DateTime<Utc> {
+        match self {
+            Self::System => Utc::now(),
+            Self::Fixed(now) => *now,
+        }
+    }
+
+    pub fn today(&self) -> NaiveDate {
+        self.now().date_naive()
+    }
+}
@@ -1,12 +1,45 @@
+use crate::cassette::Cassette;
+use crate::channel_manifest::ChannelManifestSource;
+use crate::edition::Edition;
+use crate::github_api::GitHubBackend;
+use crate::http_cache::HttpCache;
+use crate::release_notes_source::ReleaseNotesSource;
+use crate::release_schedule::{CycleEvent, EventAnchor, ScheduleOverride};
 use chrono::NaiveDate;
+use semver::Version;
+use std::path::PathBuf;
 
 #[derive(Debug, Clone)]
 pub struct Config {
     pub num_versions: usize,
-    pub rust_releases_url: String,
+    pub release_notes_source: ReleaseNotesSource,
+    /// Records or replays the HTTP exchanges with GitHub and the RELEASES.md fetch
+    pub cassette: Option<Cassette>,
+    /// Revalidates unchanged GitHub and RELEASES.md responses instead of downloading them again
+    pub http_cache: Option<HttpCache>,
+    pub channel_manifest_source: ChannelManifestSource,
     pub repo_owner: String,
     pub repo_name: String,
+    /// Whether GitHub is read with the REST or the GraphQL API
+    pub github_backend: GitHubBackend,
+    /// How many milestone issue lists are fetched at the same time
+    pub github_concurrency: usize,
+    /// How many stabilization PR searches run at the same time
+    pub github_search_concurrency: usize,
+    /// Read the GitHub data from this directory instead of the API, see [`crate::InMemoryGitHub::from_dir`]
+    pub github_fixtures: Option<PathBuf>,
+    /// Where the generated site is served, for absolute links
+    pub site_url: String,
+    /// `epoch_version` was released on `epoch_date`, the 6-week cadence is projected from there
+    pub epoch_version: Version,
     pub epoch_date: NaiveDate,
+    /// Release or branch dates which deviate from the cadence
+    pub schedule_overrides: Vec<ScheduleOverride>,
+    /// Events of every release cycle besides branching and releasing, shown on the upcoming versions. Read from
+    /// `RUST_CYCLE_EVENTS` or `RUST_CYCLE_EVENTS_FILE` when set, see [`CycleEvent::parse_list`].
+    pub cycle_events: Vec<CycleEvent>,
+    /// Editions and their stabilizing versions. Editions announced in RELEASES.md are added to these.
+    pub editions: Vec<Edition>,
     pub hugo_template_dir: String,
     pub hugo_content_dir: String,
     pub hugo_public_dir: String,
@@ -17,10 +50,43 @@ impl Config {
     pub fn new() -> Self {
         Self {
             num_versions: 5,
-            rust_releases_url: "https://raw.githubusercontent.com/rust-lang/rust/stable/RELEASES.md".to_string(),
+            release_notes_source: ReleaseNotesSource::from_env(),
+            cassette: Cassette::from_env(),
+            http_cache: HttpCache::from_env(),
+            channel_manifest_source: ChannelManifestSource::from_env(),
             repo_owner: "rust-lang".to_string(),
             repo_name: "rust".to_string(),
+            github_backend: GitHubBackend::from_env(),
+            github_concurrency: std::env::var("GITHUB_CONCURRENCY")
+                .ok()
+                .and_then(|concurrency| concurrency.trim().parse().ok())
+                .unwrap_or(4),
+            // GitHub's secondary rate limits punish concurrent searches
+            github_search_concurrency: std::env::var("GITHUB_SEARCH_CONCURRENCY")
+                .ok()
+                .and_then(|concurrency| concurrency.trim().parse().ok())
+                .unwrap_or(1),
+            github_fixtures: std::env::var_os("GITHUB_FIXTURES").map(PathBuf::from),
+            site_url: "https://releases.rs".to_string(),
+            epoch_version: Version::new(1, 5, 0),
             epoch_date: NaiveDate::from_ymd_opt(2015, 12, 10).unwrap(),
+            schedule_overrides: ScheduleOverride::from_env(),
+            cycle_events: CycleEvent::from_env().unwrap_or_else(|| {
+                vec![
+                    // The new beta is published with the previous release
+                    CycleEvent::new("Promoted to beta", EventAnchor::Branch, 6),
+                    // Backports to beta are only accepted until the week before the release
+                    CycleEvent::new("Beta backport freeze", EventAnchor::Release, -7).until(-1),
+                    CycleEvent::new("Pre-release testing on dev-static", EventAnchor::Release, -2),
+                    CycleEvent::new("Release blog post", EventAnchor::Release, 0),
+                ]
+            }),
+            editions: vec![
+                Edition::new(2015, Version::new(1, 0, 0)),
+                Edition::new(2018, Version::new(1, 31, 0)),
+                Edition::new(2021, Version::new(1, 56, 0)),
+                Edition::new(2024, Version::new(1, 85, 0)),
+            ],
             hugo_template_dir: "hugo/rust-changelogs/template".to_string(),
             hugo_content_dir: "hugo/rust-changelogs/content".to_string(),
             hugo_public_dir: "hugo/rust-changelogs/public".to_string(),
@@ -0,0 +1,117 @@
+use crate::release_catalog::ReleaseCatalog;
+use crate::release_notes::{strip_links, Item};
+use regex::Regex;
+use semver::Version;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::LazyLock;
+
+static STABILIZATION_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)\b(20\d{2}) edition is now stable|\brelease of the (20\d{2}) edition\b").unwrap()
+});
+static EDITION_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)\beditions?\b").unwrap());
+static YEAR_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\b(20\d{2})\b").unwrap());
+
+/// A Rust edition and the version which made it stable
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edition {
+    pub year: u16,
+    pub version: Version,
+}
+
+impl Edition {
+    pub fn new(year: u16, version: Version) -> Self {
+        Self { year, version }
+    }
+
+    /// Anchor of the edition on the editions page
+    pub fn anchor(&self) -> String {
+        format!("rust-{}", self.year)
+    }
+}
+
+impl fmt::Display for Edition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Rust {}", self.year)
+    }
+}
+
+/// The known editions, ordered by year
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Editions {
+    editions: BTreeMap<u16, Version>,
+}
+
+impl Editions {
+    pub fn new(editions: &[Edition]) -> Self {
+        Self {
+            editions: editions.iter().map(|e| (e.year, e.version.clone())).collect(),
+        }
+    }
+
+    /// The configured editions, plus the ones announced in RELEASES.md ("The 2024 Edition is now stable.")
+    /// which aren't configured
+    pub fn resolve(configured: &[Edition], catalog: &ReleaseCatalog) -> Self {
+        let mut editions = Self::new(configured);
+        for edition in Self::detect(catalog) {
+            editions.editions.entry(edition.year).or_insert(edition.version);
+        }
+        editions
+    }
+
+    /// Editions announced in the release notes, oldest version first
+    pub fn detect(catalog: &ReleaseCatalog) -> Vec<Edition> {
+        let mut detected = Vec::new();
+        for entry in catalog.iter() {
+            for item in entry.notes.items() {
+                let text = strip_links(&item.text);
+                let Some(caps) = STABILIZATION_RE.captures(&text) else {
+                    continue;
+                };
+                let year = caps.get(1).or(caps.get(2)).unwrap().as_str().parse().unwrap();
+                if !detected.iter().any(|e: &Edition| e.year == year) {
+                    detected.push(Edition::new(year, entry.version.clone()));
+                }
+            }
+        }
+        detected
+    }
+
+    pub fn get(&self, year: u16) -> Option<Edition> {
+        self.editions.get(&year).map(|version| Edition::new(year, version.clone()))
+    }
+
+    /// The edition made stable by `version`
+    pub fn stabilized_by(&self, version: &Version) -> Option<Edition> {
+        self.iter().find(|edition| edition.version == *version)
+    }
+
+    /// Oldest first
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = Edition> + '_ {
+        self.editions.iter().map(|(year, version)| Edition::new(*year, version.clone()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.editions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.editions.is_empty()
+    }
+
+    /// Whether `item` is about editions at all, and which of the known editions it names
+    pub fn mentioned_by(&self, item: &Item) -> Option<Vec<Edition>> {
+        let text = strip_links(&item.text);
+        if !EDITION_RE.is_match(&text) {
+            return None;
+        }
+
+        let mut editions: Vec<_> = YEAR_RE
+            .captures_iter(&text)
+            .filter_map(|caps| self.get(caps[1].parse().ok()?))
+            .collect();
+        editions.sort_by_key(|edition| edition.year);
+        editions.dedup();
+        Some(editions)
+    }
+}
@@ -0,0 +1,187 @@
+use crate::config::Config;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use futures::{stream, StreamExt};
+use octocrab::models::issues::Issue;
+use octocrab::models::{IssueId, Milestone};
+use semver::Version;
+use serde::de::DeserializeOwned;
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::path::Path;
+use std::{fs, io};
+
+/// The GitHub API which the data is read from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GitHubBackend {
+    /// [`crate::GitHubClient`]
+    #[default]
+    Rest,
+    /// [`crate::GitHubGraphQlClient`], it needs a `GITHUB_TOKEN`
+    GraphQl,
+}
+
+impl GitHubBackend {
+    /// `GITHUB_BACKEND=graphql` or `GITHUB_BACKEND=rest`, REST by default
+    pub fn from_env() -> Self {
+        match std::env::var("GITHUB_BACKEND").as_deref().map(str::to_lowercase).as_deref() {
+            Ok("graphql") => Self::GraphQl,
+            Ok("rest") | Err(_) => Self::Rest,
+            Ok(backend) => {
+                eprintln!("warning: unknown GITHUB_BACKEND '{backend}', using the REST API");
+                Self::Rest
+            }
+        }
+    }
+}
+
+/// The GitHub data the site is generated from, in the models of the REST API.
+///
+/// Ids aren't reliable across implementations: [`crate::GitHubGraphQlClient`] has no database ids for
+/// milestones and labels, so a milestone's `id` is its number and every label's `id` is 0. Milestones are
+/// identified by their number, and labels by their name.
+pub trait GitHubApi {
+    /// The milestones of the latest versions, by version
+    fn fetch_milestones(&self) -> impl Future<Output = Result<HashMap<Version, Milestone>>> + Send;
+
+    /// Open PRs whose titles start with one of the stabilization search terms
+    fn fetch_stabilization_prs(&self) -> impl Future<Output = Result<HashMap<IssueId, Issue>>> + Send;
+
+    /// The closed `relnotes` issues and PRs of a milestone
+    fn fetch_milestone_issues(&self, milestone_id: i64) -> impl Future<Output = Result<Vec<Issue>>> + Send;
+
+    /// Reports the API quota used so far
+    fn log_quota_usage(&self) {}
+
+    /// Answers as GitHub would have on `cutoff`, see the implementations for the details
+    fn with_cutoff(self, cutoff: DateTime<Utc>) -> Self
+    where
+        Self: Sized;
+}
+
+/// Fetches the issues of several milestones, `concurrency` at a time, by milestone number. A milestone which
+/// can't be fetched doesn't affect the others.
+pub async fn fetch_milestones_issues(
+    github: &impl GitHubApi,
+    milestone_ids: impl IntoIterator<Item = i64>,
+    concurrency: usize,
+) -> BTreeMap<i64, Result<Vec<Issue>>> {
+    stream::iter(milestone_ids)
+        .map(|milestone_id| async move { (milestone_id, github.fetch_milestone_issues(milestone_id).await) })
+        .buffer_unordered(concurrency.max(1))
+        .collect()
+        .await
+}
+
+/// The search for open PRs with `search_term` in their title, created by `cutoff`
+pub(crate) fn stabilization_search(config: &Config, search_term: &str, cutoff: Option<DateTime<Utc>>) -> String {
+    let mut query = format!("is:pr is:open in:title repo:{}/{} {search_term}", config.repo_owner, config.repo_name);
+    if let Some(cutoff) = cutoff {
+        query.push_str(&format!(" created:<={}", cutoff.format("%Y-%m-%dT%H:%M:%SZ")));
+    }
+    query
+}
+
+/// Whether a PR title found by [`stabilization_search`] starts with `search_term`, the search also matches it
+/// anywhere in the title
+pub(crate) fn is_stabilization_title(title: &str, search_term: &str) -> bool {
+    let title = title.to_lowercase();
+    title.starts_with(search_term)
+        || title.starts_with(&format!("partial {search_term}"))
+        || title.starts_with(&format!("partially {search_term}"))
+}
+
+/// Whether `issue` was closed by `cutoff`, always true without a cutoff
+pub(crate) fn closed_by(issue: &Issue, cutoff: Option<DateTime<Utc>>) -> bool {
+    match (cutoff, issue.closed_at) {
+        (Some(cutoff), Some(closed_at)) => closed_at <= cutoff,
+        (Some(_), None) => false,
+        (None, _) => true,
+    }
+}
+
+/// Serves GitHub API responses from memory, e.g. loaded from a fixture directory
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryGitHub {
+    pub milestones: Vec<Milestone>,
+    pub stabilization_prs: Vec<Issue>,
+    /// By milestone number
+    pub milestone_issues: HashMap<i64, Vec<Issue>>,
+    cutoff: Option<DateTime<Utc>>,
+}
+
+impl InMemoryGitHub {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads JSON in the format of the GitHub REST API: `milestones.json` with a list of milestones,
+    /// `stabilization_prs.json` with a list of PRs and `issues/{milestone number}.json` with the issues
+    /// of each milestone. Missing files are treated as empty lists.
+    pub fn from_dir(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref();
+        let mut github = Self {
+            milestones: read_json(&dir.join("milestones.json"))?.unwrap_or_default(),
+            stabilization_prs: read_json(&dir.join("stabilization_prs.json"))?.unwrap_or_default(),
+            ..Self::default()
+        };
+
+        for milestone in &github.milestones {
+            let path = dir.join("issues").join(format!("{}.json", milestone.number));
+            if let Some(issues) = read_json(&path)? {
+                github.milestone_issues.insert(milestone.number, issues);
+            }
+        }
+
+        Ok(github)
+    }
+}
+
+fn read_json<T: DeserializeOwned>(path: &Path) -> Result<Option<T>> {
+    let body = match fs::read_to_string(path) {
+        Ok(body) => body,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).with_context(|| format!("can't read {}", path.display())),
+    };
+
+    serde_json::from_str(&body).with_context(|| format!("can't parse {}", path.display())).map(Some)
+}
+
+impl GitHubApi for InMemoryGitHub {
+    /// Every milestone titled with a version, which was created by the cutoff
+    async fn fetch_milestones(&self) -> Result<HashMap<Version, Milestone>> {
+        Ok(self
+            .milestones
+            .iter()
+            .filter(|milestone| self.cutoff.is_none_or(|cutoff| milestone.created_at <= cutoff))
+            .filter_map(|milestone| Some((Version::parse(&milestone.title).ok()?, milestone.clone())))
+            .collect())
+    }
+
+    /// The PRs created by the cutoff
+    async fn fetch_stabilization_prs(&self) -> Result<HashMap<IssueId, Issue>> {
+        Ok(self
+            .stabilization_prs
+            .iter()
+            .filter(|pr| self.cutoff.is_none_or(|cutoff| pr.created_at <= cutoff))
+            .map(|pr| (pr.id, pr.clone()))
+            .collect())
+    }
+
+    /// The issues closed by the cutoff, an unknown milestone has none
+    async fn fetch_milestone_issues(&self, milestone_id: i64) -> Result<Vec<Issue>> {
+        Ok(self
+            .milestone_issues
+            .get(&milestone_id)
+            .into_iter()
+            .flatten()
+            .filter(|issue| closed_by(issue, self.cutoff))
+            .cloned()
+            .collect())
+    }
+
+    fn with_cutoff(mut self, cutoff: DateTime<Utc>) -> Self {
+        self.cutoff = Some(cutoff);
+        self
+    }
+}
@@ -1,34 +1,91 @@
 use crate::config::Config;
+use crate::github_api::{closed_by, is_stabilization_title, stabilization_search, GitHubApi};
+use crate::http_client::HttpClient;
+use crate::rate_limit::{RateLimitLayer, RateLimiter};
 use anyhow::Result;
+use chrono::{DateTime, Utc};
+use futures::{stream, StreamExt};
+use http::header::{HeaderValue, USER_AGENT};
+use http::Uri;
 use octocrab::models::issues::Issue;
 use octocrab::models::{IssueId, Milestone};
 use octocrab::params::issues::Sort;
 use octocrab::params::{issues, Direction, State};
-use octocrab::Octocrab;
+use octocrab::service::middleware::auth_header::AuthHeaderLayer;
+use octocrab::service::middleware::base_uri::BaseUriLayer;
+use octocrab::service::middleware::extra_headers::ExtraHeadersLayer;
+use octocrab::{AuthState, Octocrab, OctocrabBuilder};
 use semver::Version;
 use std::collections::HashMap;
+use std::sync::Arc;
+use tower::Layer;
 
 #[derive(Debug)]
 pub struct GitHubClient {
     octocrab: Octocrab,
+    rate_limiter: RateLimiter,
     config: Config,
+    cutoff: Option<DateTime<Utc>>,
 }
 
 impl GitHubClient {
     pub fn new(config: Config) -> Self {
-        let mut builder = Octocrab::builder();
-        let token = std::env::var("GITHUB_TOKEN").ok();
-        if let Some(token) = token {
-            builder = builder.personal_token(token);
-        }
+        let rate_limiter = RateLimiter::new();
 
         Self {
-            octocrab: builder.build().unwrap(),
+            octocrab: octocrab(&config, &rate_limiter),
+            rate_limiter,
             config,
+            cutoff: None,
         }
     }
 
-    pub async fn fetch_milestones(&self) -> Result<HashMap<Version, Milestone>> {
+    /// The open PRs whose titles start with `search_term`
+    async fn search_stabilization_prs(&self, search_term: &str) -> Result<Vec<Issue>> {
+        println!("search for {search_term} PRs");
+        let mut stabilization_prs = Vec::new();
+
+        let mut prs_page = self.octocrab
+            .search()
+            .issues_and_pull_requests(&stabilization_search(&self.config, search_term, self.cutoff))
+            .sort("created_at")
+            .order("desc")
+            .send()
+            .await?;
+
+        loop {
+            stabilization_prs.extend(prs_page.items.iter().filter(|pr| is_stabilization_title(&pr.title, search_term)).cloned());
+            prs_page = match self.octocrab.get_page::<Issue>(&prs_page.next).await? {
+                Some(next_page) => next_page,
+                None => break,
+            };
+        }
+
+        Ok(stabilization_prs)
+    }
+}
+
+/// An octocrab client with the middleware of the default one, on top of the rate limiter and [`HttpClient`] instead
+/// of hyper. `GITHUB_TOKEN` authenticates the requests.
+pub(crate) fn octocrab(config: &Config, rate_limiter: &RateLimiter) -> Octocrab {
+    let base_uri = Uri::from_static("https://api.github.com");
+    let upload_uri = Uri::from_static("https://uploads.github.com");
+    let auth_header = std::env::var("GITHUB_TOKEN")
+        .ok()
+        .map(|token| HeaderValue::from_str(&format!("Bearer {token}")).unwrap());
+
+    OctocrabBuilder::new_empty()
+        .with_service(RateLimitLayer(rate_limiter.clone()).layer(HttpClient::new(config)))
+        .with_layer(&ExtraHeadersLayer::new(Arc::new(vec![(USER_AGENT, HeaderValue::from_static("octocrab"))])))
+        .with_layer(&BaseUriLayer::new(base_uri.clone()))
+        .with_layer(&AuthHeaderLayer::new(auth_header, base_uri, upload_uri))
+        .with_auth(AuthState::None)
+        .build()
+        .unwrap()
+}
+
+impl GitHubApi for GitHubClient {
+    async fn fetch_milestones(&self) -> Result<HashMap<Version, Milestone>> {
         let mut milestones = HashMap::new();
         let mut issues_page = self.octocrab
             .issues(&self.config.repo_owner, &self.config.repo_name)
@@ -42,7 +99,7 @@ impl GitHubClient {
             .await?;
 
         'issues_pages: loop {
-            for issue in &issues_page {
+            for issue in issues_page.items.iter().filter(|issue| closed_by(issue, self.cutoff)) {
                 if let Some(version) = issue
                     .milestone
                     .as_ref()
@@ -64,45 +121,47 @@ impl GitHubClient {
         Ok(milestones.into_iter().filter_map(|(k, v)| v.map(|milestone| (k, milestone))).collect())
     }
 
-    pub async fn fetch_stabilization_prs(&self) -> Result<HashMap<IssueId, Issue>> {
-        let mut stabilization_prs = HashMap::new();
-
-        for search_term in &self.config.stabilization_search_terms {
-            println!("search for {search_term} PRs");
-
-            let mut prs_page = self.octocrab
-                .search()
-                .issues_and_pull_requests(&format!(
-                    "is:pr is:open in:title repo:{}/{} {search_term}",
-                    self.config.repo_owner, self.config.repo_name
-                ))
-                .sort("created_at")
-                .order("desc")
-                .send()
-                .await?;
-
-            loop {
-                for pr in &prs_page {
-                    let title = pr.title.to_lowercase();
-                    if title.starts_with(search_term)
-                        || title.starts_with(&format!("partial {search_term}"))
-                        || title.starts_with(&format!("partially {search_term}"))
-                    {
-                        stabilization_prs.insert(pr.id, pr.clone());
-                    }
+    /// Runs the searches one after another and merges their PRs, GitHub's secondary rate limits punish concurrent
+    /// searches
+    /// Runs at most `github_search_concurrency` searches at a time, the `search` quota and the retries on
+    /// secondary rate limits take care of the pacing
+    async fn fetch_stabilization_prs(&self) -> Result<HashMap<IssueId, Issue>> {
+        let searches: Vec<_> = self
+            .config
+            .stabilization_search_terms
+            .iter()
+            .map(|search_term| async move { (search_term, self.search_stabilization_prs(search_term).await) })
+            .collect();
+        let results: Vec<_> = stream::iter(searches)
+            .buffered(self.config.github_search_concurrency.max(1))
+            .collect()
+            .await;
+
+        let mut prs = HashMap::new();
+        let mut searched = false;
+        let mut last_error = None;
+        for (search_term, result) in results {
+            match result {
+                Ok(found) => {
+                    searched = true;
+                    prs.extend(found.into_iter().map(|pr| (pr.id, pr)));
+                }
+                Err(e) => {
+                    // The other terms still find most of the PRs
+                    eprintln!("warning: can't search stabilization PRs for '{search_term}': {e:#}");
+                    last_error = Some(e);
                 }
-                prs_page = match self.octocrab.get_page::<Issue>(&prs_page.next).await? {
-                    Some(next_page) => next_page,
-                    None => break,
-                };
-                tokio::time::sleep(std::time::Duration::from_secs(60)).await;
             }
         }
 
-        Ok(stabilization_prs)
+        // Only an error when every search failed
+        match last_error {
+            Some(e) if !searched => Err(e),
+            _ => Ok(prs),
+        }
     }
 
-    pub async fn fetch_milestone_issues(&self, milestone_id: i64) -> Result<Vec<Issue>> {
+    async fn fetch_milestone_issues(&self, milestone_id: i64) -> Result<Vec<Issue>> {
         let mut all_issues = Vec::new();
         let mut issues_page = self.octocrab
             .issues(&self.config.repo_owner, &self.config.repo_name)
@@ -117,7 +176,7 @@ impl GitHubClient {
             .await?;
 
         loop {
-            all_issues.extend(issues_page.items.clone());
+            all_issues.extend(issues_page.items.iter().filter(|issue| closed_by(issue, self.cutoff)).cloned());
             issues_page = match self.octocrab.get_page::<Issue>(&issues_page.next).await? {
                 Some(next_page) => next_page,
                 None => break,
@@ -126,4 +185,15 @@ impl GitHubClient {
 
         Ok(all_issues)
     }
-}
\ No newline at end of file
+
+    fn log_quota_usage(&self) {
+        print!("GitHub quota used:\n{}", self.rate_limiter.report());
+    }
+
+    /// Milestones and milestone issues only count issues closed by `cutoff`, and stabilization PRs have to
+    /// be created by then. PRs which were open on `cutoff` but have been closed since can't be found.
+    fn with_cutoff(mut self, cutoff: DateTime<Utc>) -> Self {
+        self.cutoff = Some(cutoff);
+        self
+    }
+}
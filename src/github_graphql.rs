@@ -0,0 +1,523 @@
+use crate::config::Config;
+use crate::github_api::{closed_by, is_stabilization_title, stabilization_search, GitHubApi};
+use crate::github_client::octocrab;
+use crate::rate_limit::RateLimiter;
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use itertools::Itertools;
+use octocrab::models::issues::Issue;
+use octocrab::models::{IssueId, Milestone};
+use octocrab::Octocrab;
+use semver::Version;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+const MILESTONE_FRAGMENT: &str = "
+fragment milestone on Milestone {
+  id number title state description url createdAt updatedAt closedAt dueOn
+}";
+
+const PULL_REQUEST_FRAGMENT: &str = "
+fragment pullRequest on PullRequest {
+  __typename databaseId id number title body url state locked authorAssociation createdAt updatedAt closedAt mergedAt
+  author { __typename login url avatarUrl ... on User { databaseId } ... on Bot { databaseId } }
+  labels(first: 100) { pageInfo { hasNextPage endCursor } nodes { id name description color isDefault } }
+  comments { totalCount }
+}";
+
+const ISSUE_FRAGMENT: &str = "
+fragment issue on Issue {
+  __typename databaseId id number title body url state locked authorAssociation createdAt updatedAt closedAt
+  author { __typename login url avatarUrl ... on User { databaseId } ... on Bot { databaseId } }
+  labels(first: 100) { pageInfo { hasNextPage endCursor } nodes { id name description color isDefault } }
+  comments { totalCount }
+}";
+
+/// Open and closed milestones, with the counts of their closed relnotes PRs and issues
+const MILESTONES_QUERY: &str = "
+query($owner: String!, $name: String!, $cursor: String) {
+  repository(owner: $owner, name: $name) {
+    milestones(first: 100, after: $cursor, orderBy: {field: CREATED_AT, direction: DESC}) {
+      pageInfo { hasNextPage endCursor }
+      nodes {
+        ...milestone
+        relnotesPullRequests: pullRequests(labels: [\"relnotes\"], states: [MERGED, CLOSED]) { totalCount }
+        relnotesIssues: issues(labels: [\"relnotes\"], states: [CLOSED]) { totalCount }
+      }
+    }
+  }
+}";
+
+/// The connections are skipped with `@include` once they are exhausted
+const MILESTONE_ISSUES_QUERY: &str = "
+query(
+  $owner: String!, $name: String!, $number: Int!,
+  $prs: Boolean!, $prsCursor: String, $issues: Boolean!, $issuesCursor: String
+) {
+  repository(owner: $owner, name: $name) {
+    milestone(number: $number) {
+      ...milestone
+      pullRequests(
+        first: 100, after: $prsCursor, labels: [\"relnotes\"], states: [MERGED, CLOSED],
+        orderBy: {field: CREATED_AT, direction: ASC}
+      ) @include(if: $prs) {
+        pageInfo { hasNextPage endCursor }
+        nodes { ...pullRequest }
+      }
+      issues(
+        first: 100, after: $issuesCursor, labels: [\"relnotes\"], states: [CLOSED],
+        orderBy: {field: CREATED_AT, direction: ASC}
+      ) @include(if: $issues) {
+        pageInfo { hasNextPage endCursor }
+        nodes { ...issue }
+      }
+    }
+  }
+}";
+
+/// Reads the GitHub data with the GraphQL API: the milestones 100 at a time, a milestone's relnotes PRs and
+/// issues 100 at a time, and all stabilization searches in one request per page. The results are converted
+/// to the REST models, so that it's interchangeable with [`crate::GitHubClient`], except for the ids noted
+/// on [`GitHubApi`]. The GraphQL API requires a `GITHUB_TOKEN`.
+#[derive(Debug)]
+pub struct GitHubGraphQlClient {
+    octocrab: Octocrab,
+    rate_limiter: RateLimiter,
+    config: Config,
+    cutoff: Option<DateTime<Utc>>,
+}
+
+impl GitHubGraphQlClient {
+    pub fn new(config: Config) -> Self {
+        let rate_limiter = RateLimiter::new();
+
+        Self {
+            octocrab: octocrab(&config, &rate_limiter),
+            rate_limiter,
+            config,
+            cutoff: None,
+        }
+    }
+
+    /// Runs `query` and returns its `data`, errors in the response fail the whole query
+    async fn query<T: DeserializeOwned>(&self, query: &str, variables: Value) -> Result<T> {
+        let mut response: Value = self.octocrab.graphql(&json!({ "query": query, "variables": variables })).await?;
+
+        if let Some(errors) = response.get("errors").and_then(Value::as_array).filter(|errors| !errors.is_empty()) {
+            bail!(
+                "GraphQL query failed: {}",
+                errors.iter().map(|error| error["message"].as_str().unwrap_or("unknown error")).join("; ")
+            );
+        }
+
+        serde_json::from_value(response["data"].take()).context("unexpected GraphQL response")
+    }
+
+    fn api_url(&self) -> String {
+        format!("https://api.github.com/repos/{}/{}", self.config.repo_owner, self.config.repo_name)
+    }
+}
+
+impl GitHubApi for GitHubGraphQlClient {
+    /// Like [`crate::GitHubClient`], the latest milestones with closed relnotes PRs or issues, open or
+    /// closed. They are taken by the creation of the milestone rather than of its newest relnotes issue, and
+    /// with a cutoff, its issues closed later still count.
+    async fn fetch_milestones(&self) -> Result<HashMap<Version, Milestone>> {
+        let mut milestones = HashMap::new();
+        let mut cursor = Cursor::default();
+
+        'pages: while !cursor.done {
+            let data: RepositoryData<MilestonesData> = self
+                .query(
+                    &format!("{MILESTONES_QUERY}{MILESTONE_FRAGMENT}"),
+                    json!({
+                        "owner": self.config.repo_owner,
+                        "name": self.config.repo_name,
+                        "cursor": cursor.after,
+                    }),
+                )
+                .await?;
+
+            for MilestoneSummary { milestone: node, relnotes_pull_requests, relnotes_issues } in
+                cursor.advance(Some(data.repository.milestones))
+            {
+                if relnotes_pull_requests.total_count + relnotes_issues.total_count == 0
+                    || self.cutoff.is_some_and(|cutoff| node.created_at > cutoff)
+                {
+                    continue;
+                }
+                if let Ok(version) = Version::parse(&node.title) {
+                    milestones.entry(version).or_insert(serde_json::from_value(node.to_rest(&self.api_url()))?);
+
+                    if milestones.len() > self.config.num_versions {
+                        break 'pages;
+                    }
+                }
+            }
+        }
+
+        Ok(milestones)
+    }
+
+    /// Searches with every term at once, and pages through the searches which have more results
+    async fn fetch_stabilization_prs(&self) -> Result<HashMap<IssueId, Issue>> {
+        let search_terms = &self.config.stabilization_search_terms;
+        let mut cursors = vec![Cursor::default(); search_terms.len()];
+        let mut stabilization_prs = HashMap::new();
+
+        loop {
+            let pending: Vec<_> = (0..search_terms.len()).filter(|&i| !cursors[i].done).collect();
+            if pending.is_empty() {
+                break;
+            }
+            println!("search for {} PRs", pending.iter().map(|&i| search_terms[i]).join(", "));
+
+            let mut variables = serde_json::Map::new();
+            for &i in &pending {
+                variables.insert(format!("q{i}"), stabilization_search(&self.config, search_terms[i], self.cutoff).into());
+                variables.insert(format!("c{i}"), cursors[i].after.clone().into());
+            }
+            let mut data: HashMap<String, Connection<Item>> =
+                self.query(&search_query(&pending), Value::Object(variables)).await?;
+
+            for i in pending {
+                let connection = data.remove(&format!("s{i}")).context("GraphQL response without search results")?;
+                for pr in cursors[i].advance(Some(connection)) {
+                    if is_stabilization_title(&pr.title, search_terms[i]) {
+                        let pr: Issue = serde_json::from_value(pr.to_rest(&self.api_url(), None))?;
+                        stabilization_prs.insert(pr.id, pr);
+                    }
+                }
+            }
+        }
+
+        Ok(stabilization_prs)
+    }
+
+    /// The closed (including merged) PRs and closed issues, by creation like the REST API. An unknown
+    /// milestone has none.
+    async fn fetch_milestone_issues(&self, milestone_id: i64) -> Result<Vec<Issue>> {
+        let (mut prs, mut issues) = (Cursor::default(), Cursor::default());
+        let mut items = Vec::new();
+        let mut milestone = Value::Null;
+
+        while !prs.done || !issues.done {
+            let data: RepositoryData<MilestoneData> = self
+                .query(
+                    &format!("{MILESTONE_ISSUES_QUERY}{MILESTONE_FRAGMENT}{PULL_REQUEST_FRAGMENT}{ISSUE_FRAGMENT}"),
+                    json!({
+                        "owner": self.config.repo_owner,
+                        "name": self.config.repo_name,
+                        "number": milestone_id,
+                        "prs": !prs.done,
+                        "prsCursor": prs.after,
+                        "issues": !issues.done,
+                        "issuesCursor": issues.after,
+                    }),
+                )
+                .await?;
+
+            let Some(node) = data.repository.milestone else {
+                return Ok(Vec::new());
+            };
+            items.extend(prs.advance(node.pull_requests));
+            items.extend(issues.advance(node.issues));
+            milestone = node.milestone.to_rest(&self.api_url());
+        }
+
+        items.sort_by_key(|item| item.created_at);
+        let mut all_issues = Vec::new();
+        for item in items {
+            let issue: Issue = serde_json::from_value(item.to_rest(&self.api_url(), Some(&milestone)))?;
+            if closed_by(&issue, self.cutoff) {
+                all_issues.push(issue);
+            }
+        }
+
+        Ok(all_issues)
+    }
+
+    fn log_quota_usage(&self) {
+        print!("GitHub quota used:\n{}", self.rate_limiter.report());
+    }
+
+    /// Like [`crate::GitHubClient`], milestone issues have to be closed and stabilization PRs created by
+    /// `cutoff`. Milestones created after it are left out.
+    fn with_cutoff(mut self, cutoff: DateTime<Utc>) -> Self {
+        self.cutoff = Some(cutoff);
+        self
+    }
+}
+
+/// One aliased search per pending search term, `s{i}` with the variables `q{i}` and `c{i}`
+fn search_query(pending: &[usize]) -> String {
+    let variables = pending.iter().map(|i| format!("$q{i}: String!, $c{i}: String")).join(", ");
+    let searches = pending
+        .iter()
+        .map(|i| {
+            format!(
+                "  s{i}: search(query: $q{i}, type: ISSUE, first: 100, after: $c{i}) {{\n    \
+                 pageInfo {{ hasNextPage endCursor }}\n    nodes {{ ...pullRequest }}\n  }}"
+            )
+        })
+        .join("\n");
+
+    format!("query({variables}) {{\n{searches}\n}}{PULL_REQUEST_FRAGMENT}")
+}
+
+/// The position in a paginated connection
+#[derive(Debug, Clone, Default)]
+struct Cursor {
+    after: Option<String>,
+    done: bool,
+}
+
+impl Cursor {
+    /// Moves past a page, `None` when the connection wasn't requested
+    fn advance<T>(&mut self, connection: Option<Connection<T>>) -> Vec<T> {
+        let Some(connection) = connection else {
+            return Vec::new();
+        };
+        self.done = !connection.page_info.has_next_page;
+        self.after = connection.page_info.end_cursor;
+        connection.nodes
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct RepositoryData<T> {
+    repository: T,
+}
+
+#[derive(Debug, Deserialize)]
+struct MilestonesData {
+    milestones: Connection<MilestoneSummary>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MilestoneSummary {
+    #[serde(flatten)]
+    milestone: MilestoneNode,
+    relnotes_pull_requests: TotalCount,
+    relnotes_issues: TotalCount,
+}
+
+#[derive(Debug, Deserialize)]
+struct MilestoneData {
+    milestone: Option<MilestoneItems>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MilestoneItems {
+    #[serde(flatten)]
+    milestone: MilestoneNode,
+    pull_requests: Option<Connection<Item>>,
+    issues: Option<Connection<Item>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Connection<T> {
+    page_info: PageInfo,
+    nodes: Vec<T>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PageInfo {
+    has_next_page: bool,
+    end_cursor: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TotalCount {
+    total_count: u32,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MilestoneNode {
+    id: String,
+    number: i64,
+    title: String,
+    state: String,
+    description: Option<String>,
+    url: String,
+    created_at: DateTime<Utc>,
+    updated_at: Option<DateTime<Utc>>,
+    closed_at: Option<DateTime<Utc>>,
+    due_on: Option<DateTime<Utc>>,
+}
+
+impl MilestoneNode {
+    /// The REST API's JSON. GraphQL has no database id for milestones, the number stands in for it, see
+    /// [`GitHubApi`].
+    fn to_rest(&self, api_url: &str) -> Value {
+        let url = format!("{api_url}/milestones/{}", self.number);
+        json!({
+            "url": url,
+            "html_url": self.url,
+            "labels_url": format!("{url}/labels"),
+            "id": self.number,
+            "node_id": self.id,
+            "number": self.number,
+            "state": self.state.to_lowercase(),
+            "title": self.title,
+            "description": self.description,
+            "created_at": self.created_at,
+            "updated_at": self.updated_at,
+            "closed_at": self.closed_at,
+            "due_on": self.due_on,
+        })
+    }
+}
+
+/// A pull request or an issue
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Item {
+    #[serde(rename = "__typename")]
+    typename: String,
+    database_id: u64,
+    id: String,
+    number: u64,
+    title: String,
+    body: Option<String>,
+    url: String,
+    state: String,
+    locked: bool,
+    author_association: String,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    closed_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    merged_at: Option<DateTime<Utc>>,
+    /// `None` for deleted accounts
+    author: Option<Actor>,
+    /// The first 100, hardly any issue has more
+    labels: Connection<LabelNode>,
+    comments: TotalCount,
+}
+
+impl Item {
+    /// The REST API's JSON for an issue, where PRs are closed when they were merged
+    fn to_rest(&self, api_url: &str, milestone: Option<&Value>) -> Value {
+        if self.labels.page_info.has_next_page {
+            eprintln!("warning: {} has more than {} labels, the others are left out", self.url, self.labels.nodes.len());
+        }
+        let url = format!("{api_url}/issues/{}", self.number);
+        let pull_request = (self.typename == "PullRequest").then(|| {
+            json!({
+                "url": format!("{api_url}/pulls/{}", self.number),
+                "html_url": self.url,
+                "diff_url": format!("{}.diff", self.url),
+                "patch_url": format!("{}.patch", self.url),
+            })
+        });
+
+        json!({
+            "id": self.database_id,
+            "node_id": self.id,
+            "url": url,
+            "repository_url": api_url,
+            "labels_url": format!("{url}/labels{{/name}}"),
+            "comments_url": format!("{url}/comments"),
+            "events_url": format!("{url}/events"),
+            "html_url": self.url,
+            "number": self.number,
+            "state": if self.state == "OPEN" { "open" } else { "closed" },
+            "title": self.title,
+            "body": self.body,
+            "user": self.author.as_ref().unwrap_or(&Actor::ghost()).to_rest(),
+            "labels": self.labels.nodes.iter().map(|label| label.to_rest(api_url)).collect::<Vec<_>>(),
+            "assignees": [],
+            "author_association": self.author_association,
+            "milestone": milestone,
+            "locked": self.locked,
+            "comments": self.comments.total_count,
+            "pull_request": pull_request,
+            "closed_at": self.merged_at.or(self.closed_at),
+            "created_at": self.created_at,
+            "updated_at": self.updated_at,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Actor {
+    #[serde(rename = "__typename")]
+    typename: String,
+    login: String,
+    url: String,
+    avatar_url: String,
+    #[serde(default)]
+    database_id: Option<u64>,
+}
+
+impl Actor {
+    /// GitHub's placeholder for deleted accounts
+    fn ghost() -> Self {
+        Self {
+            typename: "User".to_string(),
+            login: "ghost".to_string(),
+            url: "https://github.com/ghost".to_string(),
+            avatar_url: "https://avatars.githubusercontent.com/u/10137?v=4".to_string(),
+            database_id: Some(10137),
+        }
+    }
+
+    fn to_rest(&self) -> Value {
+        let url = format!("https://api.github.com/users/{}", self.login);
+        json!({
+            "login": self.login,
+            "id": self.database_id.unwrap_or_default(),
+            "node_id": "",
+            "avatar_url": self.avatar_url,
+            "gravatar_id": "",
+            "url": url,
+            "html_url": self.url,
+            "followers_url": format!("{url}/followers"),
+            "following_url": format!("{url}/following{{/other_user}}"),
+            "gists_url": format!("{url}/gists{{/gist_id}}"),
+            "starred_url": format!("{url}/starred{{/owner}}{{/repo}}"),
+            "subscriptions_url": format!("{url}/subscriptions"),
+            "organizations_url": format!("{url}/orgs"),
+            "repos_url": format!("{url}/repos"),
+            "events_url": format!("{url}/events{{/privacy}}"),
+            "received_events_url": format!("{url}/received_events"),
+            "type": self.typename,
+            "site_admin": false,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LabelNode {
+    id: String,
+    name: String,
+    description: Option<String>,
+    color: String,
+    is_default: bool,
+}
+
+impl LabelNode {
+    /// GraphQL has no database id for labels, they get 0, see [`GitHubApi`]
+    fn to_rest(&self, api_url: &str) -> Value {
+        json!({
+            "id": 0,
+            "node_id": self.id,
+            "url": format!("{api_url}/labels/{}", self.name),
+            "name": self.name,
+            "description": self.description,
+            "color": self.color,
+            "default": self.is_default,
+        })
+    }
+}
@@ -0,0 +1,123 @@
+use crate::cassette::Exchange;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use reqwest::header::{HeaderValue, IF_MODIFIED_SINCE, IF_NONE_MATCH};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+
+const DEFAULT_MAX_AGE: Duration = Duration::days(7);
+
+/// A cached GET response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    /// When the response was downloaded or last confirmed unchanged with a 304
+    validated_at: DateTime<Utc>,
+    exchange: Exchange,
+}
+
+impl CacheEntry {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.exchange
+            .headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// An on-disk cache of GET responses with an `ETag` or `Last-Modified`. The next request for the same URL
+/// is made conditional, and a 304 is answered from the cache. 304s don't count against GitHub's rate limit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpCache {
+    dir: PathBuf,
+    /// Entries which weren't validated for longer are downloaded again unconditionally
+    max_age: Duration,
+}
+
+impl HttpCache {
+    pub fn new(dir: impl Into<PathBuf>, max_age: Duration) -> Self {
+        Self { dir: dir.into(), max_age }
+    }
+
+    /// `HTTP_CACHE_DIR` enables the cache, `HTTP_CACHE_MAX_AGE` sets the expiry in seconds (a week by default)
+    pub fn from_env() -> Option<Self> {
+        let dir = std::env::var("HTTP_CACHE_DIR").ok()?;
+        let max_age = std::env::var("HTTP_CACHE_MAX_AGE")
+            .ok()
+            .and_then(|seconds| seconds.trim().parse().ok())
+            .map_or(DEFAULT_MAX_AGE, Duration::seconds);
+
+        Some(Self::new(dir, max_age))
+    }
+
+    /// Sends `request` with `send`, conditionally when a response for its URL is cached
+    pub async fn send<F, Fut>(&self, mut request: reqwest::Request, send: F) -> Result<Exchange>
+    where
+        F: FnOnce(reqwest::Request) -> Fut,
+        Fut: Future<Output = Result<Exchange>>,
+    {
+        if request.method() != reqwest::Method::GET {
+            return send(request).await;
+        }
+
+        let path = self.path(request.url().as_str());
+        let now = Utc::now();
+        // An unreadable entry is just a miss
+        let cached = fs::read_to_string(&path)
+            .ok()
+            .and_then(|body| serde_json::from_str::<CacheEntry>(&body).ok())
+            .filter(|entry| now - entry.validated_at < self.max_age);
+
+        if let Some(entry) = &cached {
+            for (validator, header) in [("etag", IF_NONE_MATCH), ("last-modified", IF_MODIFIED_SINCE)] {
+                if let Some(value) = entry.header(validator).and_then(|value| HeaderValue::from_str(value).ok()) {
+                    request.headers_mut().insert(header, value);
+                }
+            }
+        }
+
+        let exchange = send(request).await?;
+
+        let entry = match (exchange.status, cached) {
+            (304, Some(mut entry)) => {
+                // The quota headers are the only ones worth keeping from the 304
+                for (name, value) in exchange.headers.iter().filter(|(name, _)| name.starts_with("x-ratelimit-")) {
+                    entry.exchange.headers.retain(|(header, _)| header != name);
+                    entry.exchange.headers.push((name.clone(), value.clone()));
+                }
+                entry.validated_at = now;
+                entry
+            }
+            (200, _) => CacheEntry {
+                validated_at: now,
+                exchange,
+            },
+            _ => return Ok(exchange),
+        };
+
+        if entry.header("etag").is_some() || entry.header("last-modified").is_some() {
+            // The response is good either way, the next run just can't revalidate it
+            if let Err(e) = self.store(&path, &entry) {
+                eprintln!("warning: can't cache {}: {e:#}", entry.exchange.url);
+            }
+        }
+
+        Ok(entry.exchange)
+    }
+
+    fn store(&self, path: &Path, entry: &CacheEntry) -> Result<()> {
+        fs::create_dir_all(&self.dir).with_context(|| format!("creating {}", self.dir.display()))?;
+        fs::write(path, serde_json::to_string(entry)?).with_context(|| format!("writing {}", path.display()))
+    }
+
+    fn path(&self, url: &str) -> PathBuf {
+        self.dir.join(format!("{:016x}.json", fnv1a(url.as_bytes())))
+    }
+}
+
+/// A hash which stays the same across builds, unlike `DefaultHasher`
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3))
+}
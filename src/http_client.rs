@@ -0,0 +1,85 @@
+use crate::cassette::{Cassette, Exchange};
+use crate::config::Config;
+use crate::http_cache::HttpCache;
+use anyhow::{bail, Result};
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full};
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// How long a request may take until its response is complete, a multi-megabyte page included
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// A client for requests over the network, which gives up on a stalled connection instead of hanging
+pub(crate) fn network_client() -> reqwest::Client {
+    reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build().unwrap()
+}
+
+/// Sends the HTTP requests of GitHubClient and the RELEASES.md and channel manifest fetches: over the
+/// network, through the cache when configured, or through the cassette when recording or replaying. The
+/// cache is bypassed with a cassette, so that recordings hold complete responses.
+#[derive(Debug, Clone, Default)]
+pub struct HttpClient {
+    client: reqwest::Client,
+    cassette: Option<Cassette>,
+    cache: Option<HttpCache>,
+}
+
+impl HttpClient {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            client: network_client(),
+            cassette: config.cassette.clone(),
+            cache: config.http_cache.clone().filter(|_| config.cassette.is_none()),
+        }
+    }
+
+    pub async fn send(&self, request: reqwest::Request) -> Result<Exchange> {
+        match &self.cache {
+            Some(cache) => cache.send(request, |request| self.send_uncached(request)).await,
+            None => self.send_uncached(request).await,
+        }
+    }
+
+    async fn send_uncached(&self, request: reqwest::Request) -> Result<Exchange> {
+        match &self.cassette {
+            Some(cassette) => cassette.send(request).await,
+            None => Exchange::perform(&self.client, request).await,
+        }
+    }
+
+    /// GETs `url` and returns the body of a successful response
+    pub async fn get_text(&self, url: &str) -> Result<String> {
+        let exchange = self.send(self.client.get(url).build()?).await?;
+        if !(200..300).contains(&exchange.status) {
+            bail!("GET {url} returned {}", exchange.status);
+        }
+        Ok(exchange.body)
+    }
+}
+
+/// Sends octocrab's requests, at the bottom of its middleware stack
+impl tower::Service<http::Request<Full<Bytes>>> for HttpClient {
+    type Response = http::Response<Full<Bytes>>;
+    type Error = BoxError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, BoxError>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), BoxError>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: http::Request<Full<Bytes>>) -> Self::Future {
+        let client = self.clone();
+        Box::pin(async move {
+            let (parts, body) = request.into_parts();
+            let body = body.collect().await?.to_bytes();
+            let request = reqwest::Request::try_from(http::Request::from_parts(parts, body))?;
+
+            Ok(client.send(request).await?.into_response()?)
+        })
+    }
+}
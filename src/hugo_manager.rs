@@ -32,11 +32,27 @@ impl HugoManager {
         Ok(())
     }
 
+    /// Writes a standalone page, available at `/{name}/`. `name` may contain subdirectories.
+    pub fn write_page(&self, name: &str, content: &str) -> Result<()> {
+        let path = Path::new(&self.config.hugo_content_dir).join(format!("{name}.md"));
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, content)?;
+        Ok(())
+    }
+
     pub fn write_index_file(&self, content: &str) -> Result<()> {
         fs::write(format!("{}/_index.md", self.config.hugo_content_dir), content)?;
         Ok(())
     }
 
+    /// Writes a file next to the built site, it has to be written after [`Self::build_site`]
+    pub fn write_public_file(&self, name: &str, content: &str) -> Result<()> {
+        fs::write(Path::new(&self.config.hugo_public_dir).join(name), content)?;
+        Ok(())
+    }
+
     pub fn build_site(&self) -> Result<()> {
         let res = std::process::Command::new("hugo")
             .arg("--minify")
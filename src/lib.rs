@@ -1,11 +1,34 @@
+pub mod cassette;
 pub mod changelog_generator;
+pub mod channel_manifest;
+pub mod clock;
 pub mod config;
+pub mod edition;
+pub mod github_api;
 pub mod github_client;
+pub mod github_graphql;
+pub mod http_cache;
+pub mod http_client;
 pub mod hugo_manager;
+pub mod msrv;
+pub mod rate_limit;
+pub mod release_catalog;
+pub mod release_notes;
+pub mod release_notes_source;
+pub mod release_schedule;
 pub mod version_manager;
 
+pub use cassette::Cassette;
 pub use changelog_generator::ChangelogGenerator;
+pub use clock::Clock;
 pub use config::Config;
+pub use github_api::{GitHubApi, InMemoryGitHub};
 pub use github_client::GitHubClient;
+pub use github_graphql::GitHubGraphQlClient;
+pub use http_client::HttpClient;
 pub use hugo_manager::HugoManager;
+pub use msrv::MsrvCalculator;
+pub use release_catalog::ReleaseCatalog;
+pub use release_notes::ReleaseNotes;
+pub use release_notes_source::ReleaseNotesSource;
 pub use version_manager::VersionManager;
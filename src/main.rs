@@ -1,61 +1,239 @@
-use anyhow::Result;
-use chrono::Utc;
-use rust_changelogs::{ChangelogGenerator, Config, GitHubClient, HugoManager, VersionManager};
+use anyhow::{bail, Context, Result};
+use chrono::NaiveDate;
+use regex::Regex;
+use rust_changelogs::github_api::{fetch_milestones_issues, GitHubBackend};
+use rust_changelogs::release_notes::GitHubReference;
+use rust_changelogs::version_manager::{parse_lenient_version, ParsedChangelogs};
+use rust_changelogs::{
+    ChangelogGenerator, Clock, Config, GitHubApi, GitHubClient, GitHubGraphQlClient, HttpClient, HugoManager, InMemoryGitHub, MsrvCalculator, VersionManager,
+};
+use semver::Version;
 use std::collections::HashSet;
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let config = Config::new();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    match args.first().map(String::as_str) {
+        None => generate_site(config, None).await,
+        Some("as-of") => as_of(config, &args[1..]).await,
+        Some("lookup") => lookup(config, &args[1..]).await,
+        Some("checklist") => checklist(config, &args[1..]).await,
+        Some("msrv") => msrv(config, &args[1..]).await,
+        Some(command) => bail!("unknown command '{command}', expected 'as-of', 'lookup', 'checklist' or 'msrv'"),
+    }
+}
+
+async fn load_changelogs(config: &Config, version_manager: &VersionManager) -> Result<ParsedChangelogs> {
+    let body = config.release_notes_source.fetch_with(&HttpClient::new(config)).await?;
+
+    let parsed = version_manager.parse_changelogs(&body)?;
+    for diagnostic in &parsed.diagnostics {
+        eprintln!("warning: {diagnostic}");
+    }
+
+    Ok(parsed)
+}
+
+/// `lookup <ref>...` prints the version which shipped each PR or issue. A reference is either a
+/// GitHub URL, `owner/repo#number` or a bare number in the configured repo.
+async fn lookup(config: Config, refs: &[String]) -> Result<()> {
+    if refs.is_empty() {
+        bail!("usage: lookup <owner/repo#number | number | url>...");
+    }
+
+    let shorthand_re = Regex::new(r"^(?:([\w.-]+)/([\w.-]+))?#?(\d+)$").unwrap();
+    let version_manager = VersionManager::new(config.clone());
+    // Only released versions have shipped a PR, not the undated sections of upcoming ones
+    let index = load_changelogs(&config, &version_manager)
+        .await?
+        .catalog
+        .as_of(version_manager.today())
+        .reference_index();
+
+    for arg in refs {
+        let (owner, repo, number) = if let Some(reference) = GitHubReference::from_url(arg) {
+            (reference.owner, reference.repo, reference.number)
+        } else {
+            let caps = shorthand_re
+                .captures(arg)
+                .with_context(|| format!("can't parse reference '{arg}'"))?;
+            (
+                caps.get(1).map_or(config.repo_owner.clone(), |m| m.as_str().to_string()),
+                caps.get(2).map_or(config.repo_name.clone(), |m| m.as_str().to_string()),
+                caps[3].parse()?,
+            )
+        };
+
+        match index.lookup(&owner, &repo, number) {
+            Some(version) => println!("{owner}/{repo}#{number}: {version}"),
+            None => println!("{owner}/{repo}#{number}: not found in release notes"),
+        }
+    }
+
+    Ok(())
+}
+
+/// `checklist <from> <to>` prints the upgrade checklist for a toolchain bump, e.g. `checklist 1.70 1.82`
+async fn checklist(config: Config, args: &[String]) -> Result<()> {
+    let [from, to] = args else {
+        bail!("usage: checklist <from version> <to version>");
+    };
+    let from = parse_lenient_version(from).with_context(|| format!("invalid version '{from}'"))?;
+    let to = parse_lenient_version(to).with_context(|| format!("invalid version '{to}'"))?;
+
     let version_manager = VersionManager::new(config.clone());
-    let github_client = GitHubClient::new(config.clone());
     let changelog_generator = ChangelogGenerator::new(version_manager.clone());
+    let ParsedChangelogs { catalog, .. } = load_changelogs(&config, &version_manager).await?;
+
+    print!("{}", changelog_generator.generate_upgrade_checklist(&catalog, &from, &to));
+
+    Ok(())
+}
+
+/// `as-of <date>` generates the site as it looked at the end of `date` (UTC), e.g. `as-of 2024-08-01`
+async fn as_of(config: Config, args: &[String]) -> Result<()> {
+    let [date] = args else {
+        bail!("usage: as-of <yyyy-mm-dd>");
+    };
+    let date: NaiveDate = date.parse().with_context(|| format!("invalid date '{date}'"))?;
+
+    generate_site(config, Some(date)).await
+}
+
+/// `msrv <api or feature>...` prints the version which introduced each API or language feature, and the
+/// `rust-version` providing all of them, e.g. `msrv Option::take_if "const mem::size_of_val" let-else`
+async fn msrv(config: Config, queries: &[String]) -> Result<()> {
+    if queries.is_empty() {
+        bail!("usage: msrv <api path | const api path | language feature>...");
+    }
+
+    let version_manager = VersionManager::new(config.clone());
+    let ParsedChangelogs { catalog, .. } = load_changelogs(&config, &version_manager).await?;
+    let report = MsrvCalculator::new(&catalog).calculate(queries);
+
+    for requirement in &report.requirements {
+        println!("{requirement}");
+    }
+    for query in &report.unknown {
+        println!("{query}: not found in release notes");
+    }
+    if let Some(rust_version) = report.rust_version() {
+        println!("rust-version = \"{rust_version}\"");
+    }
+
+    Ok(())
+}
+
+/// Generates the site as of now, or as of the end of `as_of` with everything released or closed later
+/// left out
+async fn generate_site(config: Config, as_of: Option<NaiveDate>) -> Result<()> {
+    match &config.github_fixtures {
+        Some(dir) => {
+            let github = InMemoryGitHub::from_dir(dir)?;
+            generate_site_with(config, as_of, github).await
+        }
+        None => match config.github_backend {
+            GitHubBackend::Rest => {
+                let github = GitHubClient::new(config.clone());
+                generate_site_with(config, as_of, github).await
+            }
+            GitHubBackend::GraphQl => {
+                let github = GitHubGraphQlClient::new(config.clone());
+                generate_site_with(config, as_of, github).await
+            }
+        },
+    }
+}
+
+async fn generate_site_with(config: Config, as_of: Option<NaiveDate>, mut github: impl GitHubApi) -> Result<()> {
+    let clock = match as_of {
+        Some(date) => Clock::Fixed(date.and_hms_opt(23, 59, 59).unwrap().and_utc()),
+        None => Clock::from_env()?,
+    };
+    let mut version_manager = VersionManager::new(config.clone()).with_clock(clock);
     let hugo_manager = HugoManager::new(config.clone());
 
     hugo_manager.setup_directories()?;
 
-    let body = reqwest::get(&config.rust_releases_url)
-        .await?
-        .error_for_status()?
-        .text()
-        .await?;
+    let ParsedChangelogs { mut catalog, .. } = load_changelogs(&config, &version_manager).await?;
+    if as_of.is_some() {
+        catalog = catalog.as_of(version_manager.today());
+        github = github.with_cutoff(clock.now());
+    }
+    let milestones = github.fetch_milestones().await?;
+    let stabilization_prs = github.fetch_stabilization_prs().await?;
+    // The manifests only describe the channels of today, a past date falls back to RELEASES.md
+    let channels = match as_of {
+        Some(_) => None,
+        None => match config.channel_manifest_source.fetch_all_with(&HttpClient::new(&config)).await {
+            Ok(channels) => Some(channels),
+            Err(e) => {
+                eprintln!("warning: can't read the channel manifests: {e:#}");
+                None
+            }
+        },
+    };
 
-    let changelogs = version_manager.parse_changelogs(&body);
+    // Every page's version has to be known before weights are assigned
+    version_manager.set_known_releases(&catalog);
+    version_manager.register_versions(milestones.keys());
+    let changelog_generator = ChangelogGenerator::new(version_manager.clone());
+    let today = version_manager.today();
+
+    for (entry, release_date) in catalog.dated() {
+        let version = &entry.version;
+        let series: Vec<_> = catalog.minor_series(version.major, version.minor).filter(|e| e.release_date.is_some()).collect();
+        let patch_releases = if version.patch == 0 && version.pre.is_empty() { &series[1..] } else { &[] };
 
-    for (version, (changelog, release_date)) in changelogs.iter() {
-        let content = changelog_generator.generate_released_version_content(version, changelog, release_date);
+        let content = changelog_generator.generate_released_version_content(version, &entry.notes, &release_date, patch_releases);
         hugo_manager.write_version_file(version, &content)?;
-    }
 
-    let milestones = github_client.fetch_milestones().await?;
-    let stabilization_prs = github_client.fetch_stabilization_prs().await?;
+        if !patch_releases.is_empty() {
+            let name = format!("series/{}.{}", version.major, version.minor);
+            hugo_manager.write_page(&name, &changelog_generator.generate_series_content(&series))?;
+        }
+    }
 
-    let released_versions: HashSet<_> = changelogs
-        .iter()
-        .filter(|(_, (_, date))| *date <= Utc::now().naive_utc().date())
-        .map(|(k, _)| k.clone())
-        .collect();
+    hugo_manager.write_page("stabilized-apis", &changelog_generator.generate_stabilized_apis_content(&catalog))?;
+    hugo_manager.write_page("compatibility-notes", &changelog_generator.generate_compatibility_notes_content(&catalog))?;
+    hugo_manager.write_page("editions", &changelog_generator.generate_editions_content(&catalog))?;
 
-    let issues_versions: HashSet<_> = milestones.keys().cloned().collect();
-    let unreleased_versions: HashSet<_> = issues_versions.difference(&released_versions).collect();
+    let mut unreleased_versions: HashSet<_> = milestones.keys().filter(|v| !catalog.is_released(v, today)).collect();
 
-    let unreleased_version_to_milestone: Vec<_> = milestones
-        .into_iter()
-        .filter(|(v, _m)| unreleased_versions.contains(v))
-        .map(|(v, m)| (v, m.number))
-        .collect();
+    let (stable_version, beta_version, nightly_version) = match &channels {
+        Some(channels) => channels.current_versions(),
+        None => catalog.current_versions(today).context("no stable version in RELEASES.md")?,
+    };
 
-    let (stable_version, beta_version, nightly_version) = version_manager.get_current_versions(&changelogs);
+    let unreleased_milestones: Vec<_> = milestones.iter().filter(|(v, _)| unreleased_versions.contains(v)).collect();
+    let mut milestone_issues = fetch_milestones_issues(
+        &github,
+        unreleased_milestones.iter().map(|(_, milestone)| milestone.number),
+        config.github_concurrency,
+    )
+    .await;
 
-    for (unreleased_version, milestone_id) in unreleased_version_to_milestone.iter() {
-        let issues = github_client.fetch_milestone_issues(*milestone_id).await?;
+    for (unreleased_version, milestone) in unreleased_milestones {
+        let issues = match milestone_issues.remove(&milestone.number).unwrap() {
+            Ok(issues) => issues,
+            Err(e) => {
+                // The index doesn't link to the missing page
+                eprintln!("warning: can't fetch the issues of milestone {}: {e:#}", milestone.title);
+                unreleased_versions.remove(unreleased_version);
+                continue;
+            }
+        };
         let changelog = changelog_generator.generate_unreleased_version_content(
             unreleased_version, 
-            *milestone_id, 
+            milestone.number, 
             &stable_version, 
             &issues
         );
 
-        if !changelogs.contains_key(unreleased_version) {
+        // Versions with a scheduled date already have a page from RELEASES.md
+        if catalog.get(unreleased_version).is_none_or(|entry| entry.release_date.is_none()) {
             hugo_manager.write_version_file(unreleased_version, &changelog)?;
         }
     }
@@ -65,11 +243,19 @@ async fn main() -> Result<()> {
         &beta_version, 
         &nightly_version, 
         &unreleased_versions, 
+        channels.as_ref(),
         stabilization_prs
     );
     hugo_manager.write_index_file(&index_content)?;
+    github.log_quota_usage();
 
     hugo_manager.build_site()?;
 
+    let upcoming: Vec<_> = (1..=config.num_versions as u64)
+        .map(|i| Version::new(stable_version.major, stable_version.minor + i, 0))
+        .collect();
+    let calendar = changelog_generator.generate_calendar(&catalog, &upcoming, &config.site_url);
+    hugo_manager.write_public_file("releases.ics", &calendar)?;
+
     Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,165 @@
+use crate::release_catalog::ReleaseCatalog;
+use crate::release_notes::{strip_links, SectionKind, StabilizedApi};
+use semver::Version;
+use std::fmt;
+
+/// What a query was matched against
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchKind {
+    /// An entry of "Stabilized APIs"
+    Api,
+    /// An API which became usable in const contexts, queried as `const <path>`
+    ConstApi,
+    /// An item of the "Language" section
+    Language,
+}
+
+/// The version providing an API or language feature
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Requirement {
+    pub query: String,
+    pub version: Version,
+    pub kind: MatchKind,
+    /// The API path or the language item text which matched the query
+    pub matched: String,
+}
+
+impl fmt::Display for Requirement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} ({})", self.query, self.version, self.matched)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MsrvReport {
+    pub requirements: Vec<Requirement>,
+    /// Queries which aren't mentioned in any release, or which match several APIs or language items
+    pub unknown: Vec<String>,
+}
+
+impl MsrvReport {
+    /// The minimum version providing all the found queries
+    pub fn msrv(&self) -> Option<&Version> {
+        self.requirements.iter().map(|requirement| &requirement.version).max()
+    }
+
+    /// The MSRV as written in `rust-version` of Cargo.toml, e.g. `1.80` or `1.80.1`
+    pub fn rust_version(&self) -> Option<String> {
+        self.msrv().map(|v| match v.patch {
+            0 => format!("{}.{}", v.major, v.minor),
+            _ => v.to_string(),
+        })
+    }
+}
+
+/// Finds the first stable release providing an API path or a language feature
+#[derive(Debug, Clone)]
+pub struct MsrvCalculator {
+    /// Oldest first
+    apis: Vec<(Version, StabilizedApi)>,
+    /// Oldest first, the item text without links and backticks
+    language: Vec<(Version, String)>,
+}
+
+impl MsrvCalculator {
+    pub fn new(catalog: &ReleaseCatalog) -> Self {
+        let mut apis = Vec::new();
+        let mut language = Vec::new();
+
+        for (entry, _) in catalog.dated().filter(|(entry, _)| entry.version.pre.is_empty()) {
+            let version = &entry.version;
+            apis.extend(entry.notes.stabilized_apis().into_iter().map(|api| (version.clone(), api)));
+            for item in entry.notes.section(SectionKind::Language).into_iter().flat_map(|s| s.items()) {
+                language.push((version.clone(), strip_links(&item.text).replace('`', "")));
+            }
+        }
+
+        Self { apis, language }
+    }
+
+    /// Looks `query` up as an API path first, e.g. `Option::take_if`, `std::iter::repeat_n` or
+    /// `const mem::size_of_val`, and as whole words of a language item otherwise, e.g. `let-else`. An API
+    /// path needs at least one qualifier and matches on whole `::` segments. `None` when nothing matches,
+    /// or when the query matches several APIs or language items.
+    pub fn requirement(&self, query: &str) -> Option<Requirement> {
+        let query = query.trim().trim_matches('`');
+        let (is_const, path) = match query.strip_prefix("const ") {
+            Some(path) => (true, path.trim()),
+            None => (false, query),
+        };
+
+        let path = normalize_path(path);
+        if path.contains("::") {
+            let suffix = format!("::{path}");
+            let apis: Vec<_> = self
+                .apis
+                .iter()
+                .filter(|(_, api)| api.is_const == is_const)
+                .filter(|(_, api)| {
+                    let api_path = normalize_path(&api.path);
+                    api_path == path || api_path.ends_with(&suffix)
+                })
+                .collect();
+            if let Some((version, api)) = unambiguous(&apis, |(_, api)| normalize_path(&api.path)) {
+                return Some(Requirement {
+                    query: query.to_string(),
+                    version: version.clone(),
+                    kind: if is_const { MatchKind::ConstApi } else { MatchKind::Api },
+                    matched: api.path.clone(),
+                });
+            }
+            if !apis.is_empty() {
+                return None;
+            }
+        }
+
+        let needle = query.to_lowercase();
+        let items: Vec<_> = self
+            .language
+            .iter()
+            .filter(|(_, text)| contains_words(&text.to_lowercase(), &needle))
+            .collect();
+        unambiguous(&items, |(_, text)| text.as_str()).map(|(version, text)| Requirement {
+            query: query.to_string(),
+            version: version.clone(),
+            kind: MatchKind::Language,
+            matched: text.clone(),
+        })
+    }
+
+    pub fn calculate<S: AsRef<str>>(&self, queries: &[S]) -> MsrvReport {
+        let mut report = MsrvReport::default();
+        for query in queries {
+            match self.requirement(query.as_ref()) {
+                Some(requirement) => report.requirements.push(requirement),
+                None => report.unknown.push(query.as_ref().to_string()),
+            }
+        }
+        report
+    }
+}
+
+/// Drops the `std::`, `core::` or `alloc::` prefix, the release notes don't use them consistently
+fn normalize_path(path: &str) -> &str {
+    ["std::", "core::", "alloc::"]
+        .iter()
+        .find_map(|prefix| path.strip_prefix(prefix))
+        .unwrap_or(path)
+}
+
+/// The oldest of `hits` if they all have the same key, e.g. an API listed in several releases
+fn unambiguous<'a, T>(hits: &[&'a T], key: impl Fn(&T) -> &str) -> Option<&'a T> {
+    let first = *hits.first()?;
+    hits.iter().all(|hit| key(hit) == key(first)).then_some(first)
+}
+
+/// Whether `needle` occurs in `text` and isn't part of a longer word there
+fn contains_words(text: &str, needle: &str) -> bool {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    !needle.is_empty()
+        && text.match_indices(needle).any(|(start, _)| {
+            let end = start + needle.len();
+            text[..start].chars().next_back().is_none_or(|c| !is_word(c) || !needle.starts_with(is_word))
+                && text[end..].chars().next().is_none_or(|c| !is_word(c) || !needle.ends_with(is_word))
+        })
+}
@@ -0,0 +1,266 @@
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use http::{HeaderMap, StatusCode};
+use http_body_util::{BodyExt, Full};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Retries of a request hitting a rate limit or a transient server error, before the error response is
+/// passed on
+const MAX_RETRIES: u32 = 4;
+/// Backoff for a `502`, `503` or `504` without `Retry-After`, doubled on every retry
+const TRANSIENT_BACKOFF: Duration = Duration::from_secs(2);
+/// Backoff for secondary limits without `Retry-After`, doubled on every retry. GitHub asks for at least a
+/// minute.
+const SECONDARY_BACKOFF: Duration = Duration::from_secs(60);
+/// Slack for clock skew when waiting for a reset
+const RESET_SLACK: Duration = Duration::from_secs(1);
+
+/// The quota of one of GitHub's rate limit buckets, e.g. `core` or `search`, as of the last response
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Bucket {
+    pub limit: Option<u64>,
+    pub remaining: Option<u64>,
+    pub reset: Option<DateTime<Utc>>,
+    /// Requests made against the bucket by this process
+    pub requests: u64,
+    /// Requests which were retried because of a rate limit or a transient server error
+    pub retries: u64,
+}
+
+impl fmt::Display for Bucket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", pluralizer::pluralize("request", self.requests as isize, true))?;
+        if self.retries > 0 {
+            write!(f, " ({} retried)", self.retries)?;
+        }
+        if let (Some(remaining), Some(limit)) = (self.remaining, self.limit) {
+            write!(f, ", {remaining}/{limit} left")?;
+        }
+        if let Some(reset) = self.reset {
+            write!(f, ", resets at {}", reset.format("%H:%M:%S UTC"))?;
+        }
+        Ok(())
+    }
+}
+
+/// Schedules GitHub requests according to the rate limit headers of the previous responses: a request
+/// waits only when its bucket is exhausted, and rate limited responses are retried after `Retry-After`,
+/// the reset or a backoff. Clones share the quota state.
+#[derive(Debug, Clone, Default)]
+pub struct RateLimiter {
+    buckets: Arc<Mutex<BTreeMap<String, Bucket>>>,
+}
+
+impl RateLimiter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The bucket a request to `path` counts against. The search API has its own, smaller quota.
+    pub fn resource(path: &str) -> &'static str {
+        if path.starts_with("/search/") {
+            "search"
+        } else if path == "/graphql" {
+            "graphql"
+        } else {
+            "core"
+        }
+    }
+
+    pub fn bucket(&self, resource: &str) -> Option<Bucket> {
+        self.buckets.lock().unwrap().get(resource).cloned()
+    }
+
+    /// How long a request against `resource` has to wait for the quota to reset
+    pub fn delay_before(&self, resource: &str, now: DateTime<Utc>) -> Option<Duration> {
+        let buckets = self.buckets.lock().unwrap();
+        let bucket = buckets.get(resource)?;
+        if bucket.remaining != Some(0) {
+            return None;
+        }
+
+        let wait = (bucket.reset? - now).to_std().ok()?;
+        Some(wait + RESET_SLACK)
+    }
+
+    /// Counts a request against the remaining quota of `resource` until its response updates it, so that
+    /// concurrent requests don't all spend the last request of a bucket
+    pub fn reserve(&self, resource: &str) {
+        if let Some(remaining) = self.buckets.lock().unwrap().get_mut(resource).and_then(|bucket| bucket.remaining.as_mut()) {
+            *remaining = remaining.saturating_sub(1);
+        }
+    }
+
+    /// Records the quota from the `X-RateLimit-*` headers of a response to a request against `resource`
+    pub fn update(&self, resource: &str, headers: &HeaderMap) {
+        let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+        let number = |name: &str| header(name).and_then(|value| value.parse::<u64>().ok());
+
+        let resource = header("x-ratelimit-resource").unwrap_or(resource).to_string();
+        let mut buckets = self.buckets.lock().unwrap();
+        let bucket = buckets.entry(resource).or_default();
+        bucket.requests += 1;
+        if let Some(limit) = number("x-ratelimit-limit") {
+            bucket.limit = Some(limit);
+        }
+        if let Some(remaining) = number("x-ratelimit-remaining") {
+            bucket.remaining = Some(remaining);
+        }
+        if let Some(reset) = number("x-ratelimit-reset").and_then(|reset| DateTime::from_timestamp(reset as i64, 0)) {
+            bucket.reset = Some(reset);
+        }
+    }
+
+    /// How long to wait before retrying a response to a request against `resource`, `None` when it
+    /// shouldn't be retried. Rate limits and GitHub's occasional `502`/`503`/`504` are retried, `attempt`
+    /// is 0 for the first retry.
+    pub fn retry_delay(
+        &self,
+        resource: &str,
+        status: StatusCode,
+        headers: &HeaderMap,
+        body: &[u8],
+        attempt: u32,
+        now: DateTime<Utc>,
+    ) -> Option<Duration> {
+        let transient = matches!(status, StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE | StatusCode::GATEWAY_TIMEOUT);
+        if attempt >= MAX_RETRIES || !(transient || matches!(status, StatusCode::FORBIDDEN | StatusCode::TOO_MANY_REQUESTS)) {
+            return None;
+        }
+
+        let delay = if let Some(seconds) = headers
+            .get("retry-after")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse().ok())
+        {
+            Duration::from_secs(seconds)
+        } else if transient {
+            TRANSIENT_BACKOFF * 2u32.pow(attempt)
+        } else if let Some(delay) = self.delay_before(resource, now) {
+            delay
+        } else if status == StatusCode::TOO_MANY_REQUESTS
+            || String::from_utf8_lossy(body).to_lowercase().contains("secondary rate limit")
+        {
+            SECONDARY_BACKOFF * 2u32.pow(attempt)
+        } else {
+            // Some other permission problem
+            return None;
+        };
+
+        if let Some(bucket) = self.buckets.lock().unwrap().get_mut(resource) {
+            bucket.retries += 1;
+        }
+        Some(delay)
+    }
+
+    /// One line per bucket used, e.g. `core: 12 requests, 4988/5000 left, resets at 12:00:00 UTC`
+    pub fn report(&self) -> String {
+        self.buckets
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(resource, bucket)| format!("{resource}: {bucket}\n"))
+            .collect()
+    }
+}
+
+/// Applies a [`RateLimiter`] to a service, see [`RateLimitService`]
+#[derive(Debug, Clone)]
+pub struct RateLimitLayer(pub RateLimiter);
+
+impl<S> tower::Layer<S> for RateLimitLayer {
+    type Service = RateLimitService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimitService {
+            inner,
+            limiter: self.0.clone(),
+        }
+    }
+}
+
+/// Waits for the quota before every request, and retries rate limited responses and transient server errors
+#[derive(Debug, Clone)]
+pub struct RateLimitService<S> {
+    inner: S,
+    limiter: RateLimiter,
+}
+
+impl<S, B> tower::Service<http::Request<B>> for RateLimitService<S>
+where
+    S: tower::Service<http::Request<Full<Bytes>>, Response = http::Response<Full<Bytes>>, Error = BoxError>
+        + Clone
+        + Send
+        + 'static,
+    S::Future: Send,
+    B: http_body::Body<Data = Bytes> + Send + 'static,
+    B::Error: Into<BoxError>,
+{
+    type Response = http::Response<Full<Bytes>>;
+    type Error = BoxError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, BoxError>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), BoxError>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        let mut inner = self.inner.clone();
+        let limiter = self.limiter.clone();
+
+        Box::pin(async move {
+            let (parts, body) = request.into_parts();
+            // Kept in memory to be sent again on a retry
+            let body = body.collect().await.map_err(Into::into)?.to_bytes();
+            let resource = RateLimiter::resource(parts.uri.path());
+
+            let mut attempt = 0;
+            loop {
+                if let Some(delay) = limiter.delay_before(resource, Utc::now()) {
+                    eprintln!("GitHub {resource} quota exhausted, waiting {}s", delay.as_secs());
+                    tokio::time::sleep(delay).await;
+                }
+                limiter.reserve(resource);
+
+                let mut request = http::Request::new(Full::new(body.clone()));
+                *request.method_mut() = parts.method.clone();
+                *request.uri_mut() = parts.uri.clone();
+                *request.headers_mut() = parts.headers.clone();
+
+                let response = inner.call(request).await?;
+                limiter.update(resource, response.headers());
+
+                let (response_parts, response_body) = response.into_parts();
+                let response_body = response_body.collect().await?.to_bytes();
+                let Some(delay) = limiter.retry_delay(
+                    resource,
+                    response_parts.status,
+                    &response_parts.headers,
+                    &response_body,
+                    attempt,
+                    Utc::now(),
+                ) else {
+                    return Ok(http::Response::from_parts(response_parts, Full::new(response_body)));
+                };
+
+                eprintln!(
+                    "GitHub answered {} {} with {}, retrying in {}s",
+                    parts.method,
+                    parts.uri,
+                    response_parts.status,
+                    delay.as_secs()
+                );
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+        })
+    }
+}
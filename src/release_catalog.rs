@@ -0,0 +1,208 @@
+use crate::release_notes::ReleaseNotes;
+use chrono::NaiveDate;
+use semver::Version;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::ops::{Bound, Index};
+
+/// Where a version is in the release train on a given date
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Channel {
+    Released,
+    Beta,
+    Nightly,
+    Future,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReleaseEntry {
+    pub version: Version,
+    pub notes: ReleaseNotes,
+    /// `None` for sections without one, e.g. the upcoming version on master or beta
+    pub release_date: Option<NaiveDate>,
+}
+
+impl ReleaseEntry {
+    pub fn is_released(&self, as_of: NaiveDate) -> bool {
+        self.release_date.is_some_and(|date| date <= as_of)
+    }
+}
+
+/// All versions from RELEASES.md, ordered by version
+#[derive(Debug, Clone, Default)]
+pub struct ReleaseCatalog {
+    entries: BTreeMap<Version, ReleaseEntry>,
+}
+
+impl ReleaseCatalog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns `false` and keeps the existing entry if the version is already known
+    pub fn insert(&mut self, entry: ReleaseEntry) -> bool {
+        if self.entries.contains_key(&entry.version) {
+            return false;
+        }
+        self.entries.insert(entry.version.clone(), entry);
+        true
+    }
+
+    pub fn get(&self, version: &Version) -> Option<&ReleaseEntry> {
+        self.entries.get(version)
+    }
+
+    pub fn contains(&self, version: &Version) -> bool {
+        self.entries.contains_key(version)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Oldest first
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &ReleaseEntry> {
+        self.entries.values()
+    }
+
+    pub fn versions(&self) -> impl DoubleEndedIterator<Item = &Version> {
+        self.entries.keys()
+    }
+
+    /// Entries with a release date, oldest first. This includes releases scheduled after today.
+    pub fn dated(&self) -> impl DoubleEndedIterator<Item = (&ReleaseEntry, NaiveDate)> {
+        self.iter().filter_map(|entry| entry.release_date.map(|date| (entry, date)))
+    }
+
+    pub fn released(&self, as_of: NaiveDate) -> impl DoubleEndedIterator<Item = &ReleaseEntry> {
+        self.iter().filter(move |entry| entry.is_released(as_of))
+    }
+
+    /// The catalog as RELEASES.md listed it on `as_of`: entries released later and entries without a date
+    /// are left out
+    pub fn as_of(&self, as_of: NaiveDate) -> Self {
+        let mut catalog = Self::new();
+        for entry in self.released(as_of) {
+            catalog.insert(entry.clone());
+        }
+        catalog
+    }
+
+    pub fn is_released(&self, version: &Version, as_of: NaiveDate) -> bool {
+        self.get(version).is_some_and(|entry| entry.is_released(as_of))
+    }
+
+    pub fn previous(&self, version: &Version) -> Option<&ReleaseEntry> {
+        self.entries.range(..version).next_back().map(|(_, entry)| entry)
+    }
+
+    pub fn next(&self, version: &Version) -> Option<&ReleaseEntry> {
+        self.entries
+            .range((Bound::Excluded(version), Bound::Unbounded))
+            .next()
+            .map(|(_, entry)| entry)
+    }
+
+    /// The newest non-pre-release version released on or before `as_of`
+    pub fn latest_stable(&self, as_of: NaiveDate) -> Option<&ReleaseEntry> {
+        self.released(as_of).rev().find(|entry| entry.version.pre.is_empty())
+    }
+
+    /// `major.minor.0` and its patch releases, oldest first
+    pub fn minor_series(&self, major: u64, minor: u64) -> impl DoubleEndedIterator<Item = &ReleaseEntry> {
+        self.entries
+            .range(Version::new(major, minor, 0)..Version::new(major, minor + 1, 0))
+            .map(|(_, entry)| entry)
+            .filter(|entry| entry.version.pre.is_empty())
+    }
+
+    /// The `major.minor.x` releases after `major.minor.0`, oldest first
+    pub fn patch_releases(&self, major: u64, minor: u64) -> impl DoubleEndedIterator<Item = &ReleaseEntry> {
+        self.minor_series(major, minor).filter(|entry| entry.version.patch > 0)
+    }
+
+    /// The stable, beta and nightly versions on `as_of`. Beta and nightly are the next two minor versions.
+    pub fn current_versions(&self, as_of: NaiveDate) -> Option<(Version, Version, Version)> {
+        let stable = self.latest_stable(as_of)?.version.clone();
+        let beta = Version::new(stable.major, stable.minor + 1, 0);
+        let nightly = Version::new(stable.major, stable.minor + 2, 0);
+
+        Some((stable, beta, nightly))
+    }
+
+    /// Classifies any version, listed in the catalog or not, relative to the stable version on `as_of`
+    pub fn channel(&self, version: &Version, as_of: NaiveDate) -> Channel {
+        let Some(stable) = self.latest_stable(as_of) else {
+            return Channel::Future;
+        };
+
+        if self.is_released(version, as_of) || *version <= stable.version {
+            return Channel::Released;
+        }
+
+        if version.major != stable.version.major {
+            return Channel::Future;
+        }
+
+        // A scheduled point release of the current stable is not on any channel yet
+        match version.minor - stable.version.minor {
+            1 => Channel::Beta,
+            2 => Channel::Nightly,
+            _ => Channel::Future,
+        }
+    }
+
+    pub fn reference_index(&self) -> ReferenceIndex {
+        let mut index = ReferenceIndex::default();
+        for entry in self.iter() {
+            for reference in entry.notes.references() {
+                index
+                    .versions
+                    .entry((reference.owner.to_lowercase(), reference.repo.to_lowercase(), reference.number))
+                    .or_default()
+                    .insert(entry.version.clone());
+            }
+        }
+        index
+    }
+}
+
+impl Index<&Version> for ReleaseCatalog {
+    type Output = ReleaseEntry;
+
+    fn index(&self, version: &Version) -> &ReleaseEntry {
+        self.get(version).unwrap_or_else(|| panic!("{version} is not in the catalog"))
+    }
+}
+
+/// Maps PRs and issues to the versions whose release notes mention them
+#[derive(Debug, Clone, Default)]
+pub struct ReferenceIndex {
+    versions: HashMap<(String, String, u64), BTreeSet<Version>>,
+}
+
+impl ReferenceIndex {
+    /// All versions mentioning the PR or issue, oldest first. Backports can appear in several versions.
+    pub fn versions(&self, owner: &str, repo: &str, number: u64) -> impl Iterator<Item = &Version> {
+        self.versions
+            .get(&(owner.to_lowercase(), repo.to_lowercase(), number))
+            .into_iter()
+            .flatten()
+    }
+
+    /// The first version which shipped the PR or issue
+    pub fn lookup(&self, owner: &str, repo: &str, number: u64) -> Option<&Version> {
+        self.versions(owner, repo, number).next()
+    }
+
+    pub fn len(&self) -> usize {
+        self.versions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.versions.is_empty()
+    }
+}
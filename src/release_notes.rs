@@ -0,0 +1,624 @@
+use regex::Regex;
+use std::fmt;
+use std::sync::LazyLock;
+
+static LINK_DEFINITION_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^ {0,3}\[([^\]]+)\]:\s*(\S+)").unwrap());
+static ATX_HEADING_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^#{1,6}\s+(.*?)[\s#]*$").unwrap());
+static SETEXT_UNDERLINE_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\s{0,3}(-+|=+)\s*$").unwrap());
+static LIST_ITEM_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^(\s*)[-*+]\s+(.*)$").unwrap());
+static FENCE_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\s*(`{3,}|~{3,})").unwrap());
+static ANCHOR_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\s*<a\s+id=.*</a>\s*$").unwrap());
+static INLINE_LINK_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\]\((\S+?)\)").unwrap());
+static LINK_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\[([^\]]*)\](?:\([^)\s]*\)|\[[^\]]*\])?").unwrap());
+static LINK_LABEL_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\[([^\[\]]+)\]").unwrap());
+static GITHUB_URL_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^https?://github\.com/([^/]+)/([^/]+)/(pull|issues)/(\d+)").unwrap());
+
+/// The well-known sections of a version in RELEASES.md.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SectionKind {
+    Language,
+    Compiler,
+    Libraries,
+    StabilizedApis,
+    Cargo,
+    Rustdoc,
+    CompatibilityNotes,
+    InternalChanges,
+    Other,
+}
+
+impl SectionKind {
+    pub fn from_title(title: &str) -> Self {
+        match title.trim().to_lowercase().as_str() {
+            "language" => Self::Language,
+            "compiler" => Self::Compiler,
+            "libraries" | "library" => Self::Libraries,
+            "stabilized apis" | "stabilised apis" => Self::StabilizedApis,
+            "cargo" => Self::Cargo,
+            "rustdoc" => Self::Rustdoc,
+            // The typo does appear in older releases
+            "compatibility notes" | "compatability notes" => Self::CompatibilityNotes,
+            "internal changes" => Self::InternalChanges,
+            _ => Self::Other,
+        }
+    }
+
+    pub fn title(&self) -> Option<&'static str> {
+        match self {
+            Self::Language => Some("Language"),
+            Self::Compiler => Some("Compiler"),
+            Self::Libraries => Some("Libraries"),
+            Self::StabilizedApis => Some("Stabilized APIs"),
+            Self::Cargo => Some("Cargo"),
+            Self::Rustdoc => Some("Rustdoc"),
+            Self::CompatibilityNotes => Some("Compatibility Notes"),
+            Self::InternalChanges => Some("Internal Changes"),
+            Self::Other => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ReferenceKind {
+    PullRequest,
+    Issue,
+}
+
+/// A pull request or issue linked from a release note item
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GitHubReference {
+    pub owner: String,
+    pub repo: String,
+    pub number: u64,
+    pub kind: ReferenceKind,
+}
+
+impl GitHubReference {
+    pub fn from_url(url: &str) -> Option<Self> {
+        let caps = GITHUB_URL_RE.captures(url)?;
+
+        Some(Self {
+            owner: caps[1].to_string(),
+            repo: caps[2].to_string(),
+            number: caps[4].parse().ok()?,
+            kind: if &caps[3] == "pull" { ReferenceKind::PullRequest } else { ReferenceKind::Issue },
+        })
+    }
+}
+
+impl fmt::Display for GitHubReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}#{}", self.owner, self.repo, self.number)
+    }
+}
+
+/// A single list item, without its bullet. Continuation lines and nested lists are kept as markdown.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Item {
+    pub text: String,
+    /// PRs and issues linked from the item, both inline and through link reference definitions
+    pub references: Vec<GitHubReference>,
+}
+
+impl Item {
+    fn resolve_references(&mut self, links: &[LinkDefinition]) {
+        let inline = INLINE_LINK_RE.captures_iter(&self.text).map(|caps| caps[1].to_string());
+        let referenced = LINK_LABEL_RE
+            .captures_iter(&self.text)
+            .filter_map(|caps| find_link(links, &caps[1]).map(str::to_string));
+
+        self.references.clear();
+        for reference in inline.chain(referenced).filter_map(|url| GitHubReference::from_url(&url)) {
+            if !self.references.contains(&reference) {
+                self.references.push(reference);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Block {
+    Item(Item),
+    Text(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section {
+    pub kind: SectionKind,
+    /// `None` for the content before the first heading, e.g. patch releases which are a plain list
+    pub title: Option<String>,
+    pub blocks: Vec<Block>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkDefinition {
+    pub label: String,
+    pub url: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReleaseNotes {
+    pub sections: Vec<Section>,
+    pub links: Vec<LinkDefinition>,
+}
+
+impl Section {
+    fn new(title: Option<String>) -> Self {
+        Self {
+            kind: title.as_deref().map(SectionKind::from_title).unwrap_or(SectionKind::Other),
+            title,
+            blocks: Vec::new(),
+        }
+    }
+
+    pub fn items(&self) -> impl Iterator<Item = &Item> {
+        self.blocks.iter().filter_map(|block| match block {
+            Block::Item(item) => Some(item),
+            Block::Text(_) => None,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    /// The section as markdown, without its heading
+    pub fn body(&self) -> String {
+        let mut body = String::new();
+
+        let mut prev_was_item = None;
+        for block in &self.blocks {
+            match (block, prev_was_item) {
+                (_, None) => {}
+                (Block::Item(_), Some(true)) => body.push('\n'),
+                _ => body.push_str("\n\n"),
+            }
+            match block {
+                Block::Item(item) => body.push_str(&item.to_string()),
+                Block::Text(text) => body.push_str(text),
+            }
+            prev_was_item = Some(matches!(block, Block::Item(_)));
+        }
+
+        body
+    }
+}
+
+impl ReleaseNotes {
+    /// Parses the body of a single version, i.e. everything below its heading
+    pub fn parse(body: &str) -> Self {
+        let mut parser = Parser::default();
+        let lines: Vec<&str> = body.lines().collect();
+
+        let mut idx = 0;
+        while idx < lines.len() {
+            let line = lines[idx];
+            let next = lines.get(idx + 1).copied();
+            idx += 1;
+
+            // Code blocks are kept verbatim, their lines aren't headings, items or link definitions
+            if let Some(fence) = &parser.fence {
+                let closes = line.trim_start().strip_prefix(fence.as_str()).is_some_and(|rest| {
+                    rest.trim_start_matches(fence.chars().next().unwrap()).trim().is_empty()
+                });
+                parser.push_code_line(line);
+                if closes {
+                    parser.fence = None;
+                }
+            } else if let Some(caps) = FENCE_RE.captures(line) {
+                parser.push_line(line);
+                parser.fence = Some(caps[1].to_string());
+            } else if let Some(caps) = LINK_DEFINITION_RE.captures(line) {
+                parser.finish_block();
+                parser.notes.links.push(LinkDefinition {
+                    label: caps[1].to_string(),
+                    url: caps[2].to_string(),
+                });
+            } else if ANCHOR_RE.is_match(line) {
+                parser.finish_block();
+            } else if let Some(caps) = ATX_HEADING_RE.captures(line) {
+                parser.start_section(caps[1].to_string());
+            } else if !line.trim().is_empty()
+                && !LIST_ITEM_RE.is_match(line)
+                && next.is_some_and(|next| SETEXT_UNDERLINE_RE.is_match(next))
+            {
+                parser.start_section(line.trim().to_string());
+                idx += 1;
+            } else {
+                parser.push_line(line);
+            }
+        }
+
+        parser.finish_section();
+
+        let mut notes = parser.notes;
+        for section in &mut notes.sections {
+            for block in &mut section.blocks {
+                if let Block::Item(item) = block {
+                    item.resolve_references(&notes.links);
+                }
+            }
+        }
+
+        notes
+    }
+
+    pub fn section(&self, kind: SectionKind) -> Option<&Section> {
+        self.sections.iter().find(|section| section.kind == kind)
+    }
+
+    pub fn items(&self) -> impl Iterator<Item = &Item> {
+        self.sections.iter().flat_map(|section| section.items())
+    }
+
+    pub fn link(&self, label: &str) -> Option<&str> {
+        find_link(&self.links, label)
+    }
+
+    /// The first line of the first entry as plain text, e.g. the main fix of a patch release
+    pub fn summary(&self) -> Option<String> {
+        let first = self.sections.iter().find_map(|section| section.blocks.first())?;
+        let text = match first {
+            Block::Item(item) => &item.text,
+            Block::Text(text) => text,
+        };
+
+        Some(strip_links(text.lines().next()?).trim().to_string())
+    }
+
+    /// The link reference definitions needed to render `items` on their own
+    pub fn links_used_by<'a>(&'a self, items: &[&Item]) -> Vec<&'a LinkDefinition> {
+        self.links
+            .iter()
+            .filter(|link| {
+                items.iter().any(|item| {
+                    LINK_LABEL_RE
+                        .captures_iter(&item.text)
+                        .any(|caps| caps[1].eq_ignore_ascii_case(&link.label))
+                })
+            })
+            .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sections.iter().all(Section::is_empty)
+    }
+
+    pub fn references(&self) -> impl Iterator<Item = &GitHubReference> {
+        self.items().flat_map(|item| item.references.iter())
+    }
+
+    /// The APIs listed under "Stabilized APIs", including the lists of APIs which became usable in const
+    /// contexts. Older releases put the latter under "Libraries".
+    pub fn stabilized_apis(&self) -> Vec<StabilizedApi> {
+        let mut apis = Vec::new();
+
+        for section in &self.sections {
+            if !matches!(section.kind, SectionKind::StabilizedApis | SectionKind::Libraries) {
+                continue;
+            }
+
+            let mut is_const = false;
+            for block in &section.blocks {
+                match block {
+                    Block::Text(text) => is_const = text.to_lowercase().contains("const"),
+                    Block::Item(item) if section.kind == SectionKind::StabilizedApis || is_const => {
+                        apis.extend(StabilizedApi::from_item(item, &self.links, is_const));
+                    }
+                    Block::Item(_) => {}
+                }
+            }
+        }
+
+        apis
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = self
+            .sections
+            .iter()
+            .map(Section::to_string)
+            .filter(|s| !s.trim().is_empty())
+            .collect::<Vec<_>>()
+            .join("\n\n");
+
+        if !self.links.is_empty() {
+            markdown.push_str("\n\n");
+            for link in &self.links {
+                markdown.push_str(&format!("[{}]: {}\n", link.label, link.url));
+            }
+        }
+
+        markdown
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ApiKind {
+    Module,
+    Struct,
+    Union,
+    Enum,
+    Variant,
+    Trait,
+    TraitImpl,
+    Function,
+    Method,
+    Macro,
+    Constant,
+    TypeAlias,
+    Primitive,
+    Other,
+}
+
+impl ApiKind {
+    fn from_docs_url(url: &str) -> Self {
+        let (page, fragment) = url.split_once('#').unwrap_or((url, ""));
+        let page = page.rsplit('/').next().unwrap_or_default();
+
+        let fragment_kind = [
+            ("method.", Self::Method),
+            ("tymethod.", Self::Method),
+            ("variant.", Self::Variant),
+            ("associatedconstant.", Self::Constant),
+            ("associatedtype.", Self::TypeAlias),
+            ("impl-", Self::TraitImpl),
+        ];
+        let page_kind = [
+            ("fn.", Self::Function),
+            ("struct.", Self::Struct),
+            ("union.", Self::Union),
+            ("enum.", Self::Enum),
+            ("trait.", Self::Trait),
+            ("macro.", Self::Macro),
+            ("constant.", Self::Constant),
+            ("type.", Self::TypeAlias),
+            ("primitive.", Self::Primitive),
+            ("index.html", Self::Module),
+        ];
+
+        fragment_kind
+            .into_iter()
+            .find(|(prefix, _)| fragment.starts_with(prefix))
+            .or_else(|| page_kind.into_iter().find(|(prefix, _)| page.starts_with(prefix)))
+            .map_or(Self::Other, |(_, kind)| kind)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Module => "module",
+            Self::Struct => "struct",
+            Self::Union => "union",
+            Self::Enum => "enum",
+            Self::Variant => "variant",
+            Self::Trait => "trait",
+            Self::TraitImpl => "trait impl",
+            Self::Function => "function",
+            Self::Method => "method",
+            Self::Macro => "macro",
+            Self::Constant => "constant",
+            Self::TypeAlias => "type",
+            Self::Primitive => "primitive",
+            Self::Other => "other",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StabilizedApi {
+    /// The API as written in the release notes, without backticks, e.g. `Option::take_if`
+    pub path: String,
+    pub kind: ApiKind,
+    pub docs_url: Option<String>,
+    /// Whether the API became usable in const contexts, rather than stable
+    pub is_const: bool,
+}
+
+impl StabilizedApi {
+    fn from_item(item: &Item, links: &[LinkDefinition], is_const: bool) -> Option<Self> {
+        let (label, target) = leading_link(&item.text)?;
+        let docs_url = match target {
+            LinkTarget::Inline(url) => Some(url.to_string()),
+            LinkTarget::Reference(label) => find_link(links, label).map(str::to_string),
+        };
+
+        let path = match label.split('`').nth(1) {
+            Some(code) if !code.is_empty() => code,
+            _ => label,
+        }
+        .trim()
+        .to_string();
+
+        let kind = if path.starts_with("impl ") {
+            ApiKind::TraitImpl
+        } else {
+            docs_url.as_deref().map_or(ApiKind::Other, ApiKind::from_docs_url)
+        };
+
+        Some(Self {
+            path,
+            kind,
+            docs_url,
+            is_const,
+        })
+    }
+}
+
+enum LinkTarget<'a> {
+    Inline(&'a str),
+    Reference(&'a str),
+}
+
+/// Splits a leading `[label](url)`, `[label][ref]` or `[label]` link. Brackets inside backticks, like
+/// in `` [`<[T]>::first_chunk`] ``, don't count.
+fn leading_link(text: &str) -> Option<(&str, LinkTarget<'_>)> {
+    let label_end = matching_close(text, '[', ']')?;
+    let label = &text[1..label_end];
+    let rest = &text[label_end + 1..];
+
+    let target = if rest.starts_with('(') {
+        LinkTarget::Inline(rest[1..matching_close(rest, '(', ')')?].trim())
+    } else if rest.starts_with('[') && !rest.starts_with("[]") {
+        LinkTarget::Reference(&rest[1..matching_close(rest, '[', ']')?])
+    } else {
+        LinkTarget::Reference(label)
+    };
+
+    Some((label, target))
+}
+
+/// Replaces markdown links with their text
+pub fn strip_links(text: &str) -> String {
+    LINK_RE.replace_all(text, "$1").into_owned()
+}
+
+fn matching_close(text: &str, open: char, close: char) -> Option<usize> {
+    if !text.starts_with(open) {
+        return None;
+    }
+
+    let mut depth = 0;
+    let mut in_code = false;
+    for (idx, c) in text.char_indices() {
+        match c {
+            '`' => in_code = !in_code,
+            c if c == open && !in_code => depth += 1,
+            c if c == close && !in_code => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(idx);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+fn find_link<'a>(links: &'a [LinkDefinition], label: &str) -> Option<&'a str> {
+    links
+        .iter()
+        .find(|link| link.label.eq_ignore_ascii_case(label))
+        .map(|link| link.url.as_str())
+}
+
+impl fmt::Display for Item {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "- {}", self.text)
+    }
+}
+
+impl fmt::Display for Section {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(title) = &self.title {
+            writeln!(f, "{title}")?;
+            writeln!(f, "{}", "-".repeat(title.chars().count().max(3)))?;
+        }
+
+        write!(f, "{}", self.body())
+    }
+}
+
+enum OpenBlock {
+    Item { indent: usize, lines: Vec<String>, pending_blanks: usize },
+    Text(Vec<String>),
+}
+
+#[derive(Default)]
+struct Parser {
+    notes: ReleaseNotes,
+    section: Option<Section>,
+    block: Option<OpenBlock>,
+    /// The opening fence of the code block the parser is in, e.g. ```` ``` ````
+    fence: Option<String>,
+}
+
+impl Parser {
+    fn current_section(&mut self) -> &mut Section {
+        self.section.get_or_insert_with(|| Section::new(None))
+    }
+
+    fn start_section(&mut self, title: String) {
+        self.finish_section();
+        self.section = Some(Section::new(Some(title)));
+    }
+
+    fn finish_section(&mut self) {
+        self.finish_block();
+        if let Some(section) = self.section.take() {
+            if section.title.is_some() || !section.is_empty() {
+                self.notes.sections.push(section);
+            }
+        }
+    }
+
+    fn finish_block(&mut self) {
+        let block = match self.block.take() {
+            Some(OpenBlock::Item { lines, .. }) => Block::Item(Item {
+                text: lines.join("\n"),
+                references: Vec::new(),
+            }),
+            Some(OpenBlock::Text(lines)) => Block::Text(lines.join("\n")),
+            None => return,
+        };
+        self.current_section().blocks.push(block);
+    }
+
+    fn push_line(&mut self, line: &str) {
+        let indent = line.len() - line.trim_start().len();
+        let is_blank = line.trim().is_empty();
+        let list_item = LIST_ITEM_RE.captures(line);
+
+        match &mut self.block {
+            Some(OpenBlock::Item { indent: item_indent, lines, pending_blanks }) => {
+                if is_blank {
+                    *pending_blanks += 1;
+                    return;
+                }
+                let nested = indent > *item_indent;
+                let lazy = *pending_blanks == 0 && list_item.is_none();
+                if nested || lazy {
+                    lines.extend(std::iter::repeat_n(String::new(), *pending_blanks));
+                    *pending_blanks = 0;
+                    let strip = indent.min(*item_indent);
+                    lines.push(line[strip..].to_string());
+                    return;
+                }
+            }
+            Some(OpenBlock::Text(lines)) if !is_blank && list_item.is_none() => {
+                lines.push(line.trim_end().to_string());
+                return;
+            }
+            _ => {}
+        }
+
+        self.finish_block();
+        if let Some(caps) = list_item {
+            self.block = Some(OpenBlock::Item {
+                indent: caps[1].len(),
+                lines: vec![caps[2].trim_end().to_string()],
+                pending_blanks: 0,
+            });
+        } else if !is_blank {
+            // Leading whitespace is kept, e.g. for indented code blocks
+            self.block = Some(OpenBlock::Text(vec![line.trim_end().to_string()]));
+        }
+    }
+
+    /// A line inside a fenced code block, which belongs to the block the fence was opened in
+    fn push_code_line(&mut self, line: &str) {
+        match &mut self.block {
+            Some(OpenBlock::Item { indent, lines, pending_blanks }) => {
+                lines.extend(std::iter::repeat_n(String::new(), *pending_blanks));
+                *pending_blanks = 0;
+                let strip = (line.len() - line.trim_start().len()).min(*indent);
+                lines.push(line[strip..].trim_end().to_string());
+            }
+            Some(OpenBlock::Text(lines)) => lines.push(line.trim_end().to_string()),
+            None => self.block = Some(OpenBlock::Text(vec![line.trim_end().to_string()])),
+        }
+    }
+}
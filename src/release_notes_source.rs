@@ -0,0 +1,85 @@
+use crate::http_client::HttpClient;
+use anyhow::{bail, Context, Result};
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+pub const DEFAULT_RELEASES_URL: &str = "https://raw.githubusercontent.com/rust-lang/rust/stable/RELEASES.md";
+
+/// Where to read RELEASES.md from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReleaseNotesSource {
+    Url(String),
+    File(PathBuf),
+    /// `RELEASES.md` in a directory, e.g. a rust-lang/rust checkout
+    Directory(PathBuf),
+    /// `path` as of `revision` in the local git checkout at `repo`, e.g. a rust-lang/rust clone
+    GitRevision {
+        repo: PathBuf,
+        revision: String,
+        path: String,
+    },
+}
+
+impl ReleaseNotesSource {
+    /// Picks the source from the environment:
+    /// - `RUST_RELEASES_FILE` reads a local file
+    /// - `RUST_RELEASES_DIR` reads `RELEASES.md` from a local directory
+    /// - `RUST_RELEASES_GIT_REPO` reads `RELEASES.md` from a local checkout, at `RUST_RELEASES_GIT_REV`
+    ///   (`HEAD` by default)
+    /// - `RUST_RELEASES_URL` overrides the default URL
+    pub fn from_env() -> Self {
+        if let Ok(path) = std::env::var("RUST_RELEASES_FILE") {
+            return Self::File(path.into());
+        }
+
+        if let Ok(dir) = std::env::var("RUST_RELEASES_DIR") {
+            return Self::Directory(dir.into());
+        }
+
+        if let Ok(repo) = std::env::var("RUST_RELEASES_GIT_REPO") {
+            return Self::GitRevision {
+                repo: repo.into(),
+                revision: std::env::var("RUST_RELEASES_GIT_REV").unwrap_or_else(|_| "HEAD".to_string()),
+                path: "RELEASES.md".to_string(),
+            };
+        }
+
+        Self::Url(std::env::var("RUST_RELEASES_URL").unwrap_or_else(|_| DEFAULT_RELEASES_URL.to_string()))
+    }
+
+    pub async fn fetch(&self) -> Result<String> {
+        self.fetch_with(&HttpClient::default()).await
+    }
+
+    /// Fetches a URL with `client`, i.e. through its cache or cassette
+    pub async fn fetch_with(&self, client: &HttpClient) -> Result<String> {
+        match self {
+            Self::Url(url) => client.get_text(url).await,
+            Self::File(path) => fs::read_to_string(path).with_context(|| format!("reading {}", path.display())),
+            Self::Directory(dir) => {
+                let path = dir.join("RELEASES.md");
+                fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()))
+            }
+            Self::GitRevision { repo, revision, path } => {
+                let output = Command::new("git")
+                    .arg("-C")
+                    .arg(repo)
+                    .arg("show")
+                    .arg(format!("{revision}:{path}"))
+                    .output()
+                    .context("running git")?;
+
+                if !output.status.success() {
+                    bail!(
+                        "git show {revision}:{path} failed in {}: {}",
+                        repo.display(),
+                        String::from_utf8_lossy(&output.stderr).trim()
+                    );
+                }
+
+                Ok(String::from_utf8(output.stdout)?)
+            }
+        }
+    }
+}
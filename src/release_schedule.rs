@@ -0,0 +1,363 @@
+use crate::release_catalog::ReleaseCatalog;
+use crate::version_manager::parse_lenient_version;
+use anyhow::{bail, Context, Result};
+use chrono::{Duration, NaiveDate};
+use semver::Version;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+const CYCLE: Duration = Duration::weeks(6);
+/// Beta branches off master on the Friday before the previous release
+const BRANCH_OFFSET: Duration = Duration::days(6);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReleaseDate {
+    pub release_date: NaiveDate,
+    pub branch_date: NaiveDate,
+    /// The configured cycle events of the release, in date order
+    pub events: Vec<ScheduledEvent>,
+}
+
+/// The date of a release a [`CycleEvent`] is relative to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventAnchor {
+    Branch,
+    Release,
+}
+
+/// An event which happens in every release cycle, e.g. the pre-release testing two days before the release
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CycleEvent {
+    pub name: String,
+    pub anchor: EventAnchor,
+    /// Days after the anchor, negative for days before it
+    pub offset_days: i64,
+    /// Last day of an event spanning several days, like `offset_days`
+    pub end_offset_days: Option<i64>,
+}
+
+impl CycleEvent {
+    pub fn new(name: &str, anchor: EventAnchor, offset_days: i64) -> Self {
+        Self {
+            name: name.to_string(),
+            anchor,
+            offset_days,
+            end_offset_days: None,
+        }
+    }
+
+    /// Makes the event last until `end_offset_days` after the anchor
+    pub fn until(mut self, end_offset_days: i64) -> Self {
+        self.end_offset_days = Some(end_offset_days);
+        self
+    }
+
+    /// Reads the events from `RUST_CYCLE_EVENTS`, or from the file at `RUST_CYCLE_EVENTS_FILE`, see
+    /// [`CycleEvent::parse_list`]. `None` when neither is set or the list is invalid, which is reported.
+    pub fn from_env() -> Option<Vec<Self>> {
+        let (origin, list) = if let Ok(list) = std::env::var("RUST_CYCLE_EVENTS") {
+            ("RUST_CYCLE_EVENTS".to_string(), Ok(list))
+        } else if let Ok(path) = std::env::var("RUST_CYCLE_EVENTS_FILE") {
+            let list = std::fs::read_to_string(&path).with_context(|| format!("reading {path}"));
+            (path, list)
+        } else {
+            return None;
+        };
+
+        match list.and_then(|list| Self::parse_list(&list)) {
+            Ok(events) => Some(events),
+            Err(e) => {
+                eprintln!("warning: ignoring the cycle events in {origin}: {e:#}");
+                None
+            }
+        }
+    }
+
+    /// One event per line or `;`-separated, the anchor and offset followed by the name, e.g.
+    /// `release-2 Pre-release testing` or `release-7..-1 Beta backport freeze` for several days. Blank
+    /// lines and lines starting with `#` are skipped.
+    pub fn parse_list(list: &str) -> Result<Vec<Self>> {
+        list.split(['\n', ';'])
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(str::parse)
+            .collect()
+    }
+
+    pub fn date(&self, release_date: NaiveDate, branch_date: NaiveDate) -> NaiveDate {
+        self.anchor_date(release_date, branch_date) + Duration::days(self.offset_days)
+    }
+
+    /// The last day of an event spanning several days
+    pub fn end_date(&self, release_date: NaiveDate, branch_date: NaiveDate) -> Option<NaiveDate> {
+        self.end_offset_days
+            .map(|offset| self.anchor_date(release_date, branch_date) + Duration::days(offset))
+    }
+
+    fn anchor_date(&self, release_date: NaiveDate, branch_date: NaiveDate) -> NaiveDate {
+        match self.anchor {
+            EventAnchor::Branch => branch_date,
+            EventAnchor::Release => release_date,
+        }
+    }
+}
+
+impl FromStr for CycleEvent {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (timing, name) = s.split_once(char::is_whitespace).with_context(|| format!("expected a name after '{s}'"))?;
+        let (start, end) = match timing.split_once("..") {
+            Some((start, end)) => (start, Some(end)),
+            None => (timing, None),
+        };
+        let (anchor, offset) = if let Some(offset) = start.strip_prefix("release") {
+            (EventAnchor::Release, offset)
+        } else if let Some(offset) = start.strip_prefix("branch") {
+            (EventAnchor::Branch, offset)
+        } else {
+            bail!("unknown anchor in '{timing}', expected 'release' or 'branch'");
+        };
+        let parse_offset = |offset: &str| -> Result<i64> {
+            match offset {
+                "" => Ok(0),
+                _ => offset
+                    .strip_prefix('+')
+                    .unwrap_or(offset)
+                    .parse()
+                    .with_context(|| format!("invalid offset '{offset}', expected e.g. '+6' or '-7'")),
+            }
+        };
+
+        let mut event = Self::new(name.trim(), anchor, parse_offset(offset)?);
+        if let Some(end) = end {
+            let end = parse_offset(end)?;
+            if end < event.offset_days {
+                bail!("'{timing}' ends before it starts");
+            }
+            event = event.until(end);
+        }
+
+        Ok(event)
+    }
+}
+
+/// A cycle event of one release
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScheduledEvent {
+    pub name: String,
+    pub date: NaiveDate,
+    /// The last day of an event spanning several days
+    pub end_date: Option<NaiveDate>,
+}
+
+/// Pins the dates of a single `major.minor` release, e.g. when the release team shifts it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScheduleOverride {
+    pub version: Version,
+    pub release_date: Option<NaiveDate>,
+    pub branch_date: Option<NaiveDate>,
+}
+
+impl ScheduleOverride {
+    /// Reads the overrides from `RUST_SCHEDULE_OVERRIDES`, or from the file at `RUST_SCHEDULE_OVERRIDES_FILE`,
+    /// see [`ScheduleOverride::parse_list`]. Invalid overrides are reported and ignored.
+    pub fn from_env() -> Vec<Self> {
+        let (origin, list) = if let Ok(list) = std::env::var("RUST_SCHEDULE_OVERRIDES") {
+            ("RUST_SCHEDULE_OVERRIDES".to_string(), Ok(list))
+        } else if let Ok(path) = std::env::var("RUST_SCHEDULE_OVERRIDES_FILE") {
+            let list = std::fs::read_to_string(&path).with_context(|| format!("reading {path}"));
+            (path, list)
+        } else {
+            return Vec::new();
+        };
+
+        match list.and_then(|list| Self::parse_list(&list)) {
+            Ok(overrides) => overrides,
+            Err(e) => {
+                eprintln!("warning: ignoring the schedule overrides in {origin}: {e:#}");
+                Vec::new()
+            }
+        }
+    }
+
+    /// One override per line or `;`-separated, e.g. `1.87 release=2025-05-16 branch=2025-03-28`. Blank lines
+    /// and lines starting with `#` are skipped.
+    pub fn parse_list(list: &str) -> Result<Vec<Self>> {
+        list.split(['\n', ';'])
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(str::parse)
+            .collect()
+    }
+}
+
+impl FromStr for ScheduleOverride {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut fields = s.split_whitespace();
+        let version = fields.next().unwrap_or_default();
+        let mut schedule_override = Self {
+            version: parse_lenient_version(version).with_context(|| format!("invalid version '{version}'"))?,
+            release_date: None,
+            branch_date: None,
+        };
+
+        for field in fields {
+            let (key, date) = field.split_once('=').with_context(|| format!("expected key=date, got '{field}'"))?;
+            let date = Some(date.parse().with_context(|| format!("invalid date '{date}'"))?);
+            match key {
+                "release" => schedule_override.release_date = date,
+                "branch" => schedule_override.branch_date = date,
+                _ => bail!("unknown key '{key}', expected 'release' or 'branch'"),
+            }
+        }
+
+        Ok(schedule_override)
+    }
+}
+
+/// Dates of the `major.minor.0` releases. Explicit overrides win over dates from RELEASES.md, which
+/// win over the 6-week cadence. A point release listed in RELEASES.md has its own release date, other
+/// versions get the dates of their `.0` release. The cadence is projected from the newest release with a known date,
+/// or from the epoch when none is known.
+#[derive(Debug, Clone)]
+pub struct ReleaseSchedule {
+    epoch_version: Version,
+    epoch_date: NaiveDate,
+    overrides: BTreeMap<(u64, u64), ScheduleOverride>,
+    events: Vec<CycleEvent>,
+    known: BTreeMap<(u64, u64), NaiveDate>,
+    /// Every dated release, including point releases
+    releases: BTreeMap<Version, NaiveDate>,
+}
+
+impl ReleaseSchedule {
+    pub fn new(epoch_version: Version, epoch_date: NaiveDate, overrides: &[ScheduleOverride], events: &[CycleEvent]) -> Self {
+        Self {
+            epoch_version,
+            epoch_date,
+            overrides: overrides
+                .iter()
+                .map(|o| ((o.version.major, o.version.minor), o.clone()))
+                .collect(),
+            events: events.to_vec(),
+            known: BTreeMap::new(),
+            releases: BTreeMap::new(),
+        }
+    }
+
+    /// Records the release dates from the catalog
+    pub fn set_known_releases(&mut self, catalog: &ReleaseCatalog) {
+        self.releases = catalog
+            .dated()
+            .filter(|(entry, _)| entry.version.pre.is_empty())
+            .map(|(entry, date)| (entry.version.clone(), date))
+            .collect();
+        self.known = self
+            .releases
+            .iter()
+            .filter(|(version, _)| version.patch == 0)
+            .map(|(version, date)| ((version.major, version.minor), *date))
+            .collect();
+    }
+
+    pub fn release_date(&self, version: &Version) -> NaiveDate {
+        let key = (version.major, version.minor);
+
+        if version.patch > 0 {
+            if let Some(date) = self.releases.get(version) {
+                return *date;
+            }
+        }
+
+        if let Some(date) = self.overrides.get(&key).and_then(|o| o.release_date) {
+            return date;
+        }
+        if let Some(date) = self.known.get(&key) {
+            return *date;
+        }
+
+        let (anchor_minor, anchor_date) = self
+            .known
+            .range((version.major, 0)..key)
+            .next_back()
+            .map(|((_, minor), date)| (*minor, *date))
+            .unwrap_or((self.epoch_version.minor, self.epoch_date));
+
+        anchor_date + CYCLE * (version.minor as i32 - anchor_minor as i32)
+    }
+
+    pub fn branch_date(&self, version: &Version) -> NaiveDate {
+        if let Some(date) = self
+            .overrides
+            .get(&(version.major, version.minor))
+            .and_then(|o| o.branch_date)
+        {
+            return date;
+        }
+
+        let previous_release = match version.minor.checked_sub(1) {
+            Some(minor) => self.release_date(&Version::new(version.major, minor, 0)),
+            None => self.release_date(version) - CYCLE,
+        };
+
+        previous_release - BRANCH_OFFSET
+    }
+
+    pub fn dates(&self, version: &Version) -> ReleaseDate {
+        self.with_events(self.release_date(version), self.branch_date(version))
+    }
+
+    /// Schedules the cycle events around the given release and branch dates
+    pub fn with_events(&self, release_date: NaiveDate, branch_date: NaiveDate) -> ReleaseDate {
+        let mut events: Vec<_> = self
+            .events
+            .iter()
+            .map(|event| ScheduledEvent {
+                name: event.name.clone(),
+                date: event.date(release_date, branch_date),
+                end_date: event.end_date(release_date, branch_date),
+            })
+            .collect();
+        events.sort_by_key(|event| event.date);
+
+        ReleaseDate {
+            release_date,
+            branch_date,
+            events,
+        }
+    }
+
+    /// The stable, beta and nightly versions on `date`. Stable is the newest known point release of
+    /// its series, beta and nightly are always `.0`. `None` before the first release of the epoch's major
+    /// version.
+    pub fn versions_on(&self, date: NaiveDate) -> Option<(Version, Version, Version)> {
+        let major = self.epoch_version.major;
+        let release = |minor: u64| self.release_date(&Version::new(major, minor, 0));
+
+        // Start from the cadence estimate, then correct for slips and overrides
+        let cycles = (date - self.epoch_date).num_days().div_euclid(CYCLE.num_days());
+        let mut minor = (self.epoch_version.minor as i64 + cycles).max(0) as u64;
+        while minor > 0 && release(minor) > date {
+            minor -= 1;
+        }
+        if release(minor) > date {
+            return None;
+        }
+        while release(minor + 1) <= date {
+            minor += 1;
+        }
+
+        let stable = self
+            .releases
+            .range(Version::new(major, minor, 0)..Version::new(major, minor + 1, 0))
+            .filter(|(_, release_date)| **release_date <= date)
+            .map(|(version, _)| version.clone())
+            .next_back()
+            .unwrap_or_else(|| Version::new(major, minor, 0));
+
+        Some((stable, Version::new(major, minor + 1, 0), Version::new(major, minor + 2, 0)))
+    }
+}
@@ -1,28 +1,57 @@
+use crate::clock::Clock;
 use crate::config::Config;
-use chrono::{Duration, NaiveDate, Utc};
-use regex::RegexBuilder;
+use crate::edition::Editions;
+use crate::release_catalog::{ReleaseCatalog, ReleaseEntry};
+use crate::release_notes::ReleaseNotes;
+use crate::release_schedule::ReleaseSchedule;
+use chrono::NaiveDate;
+use anyhow::{bail, Result};
+use regex::Regex;
 use semver::Version;
-use std::collections::HashMap;
-use tap::Tap;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::ops::Bound;
 
-#[derive(Debug, Clone)]
-pub struct ReleaseDate {
-    pub release_date: NaiveDate,
-    pub branch_date: NaiveDate,
+pub use crate::release_schedule::ReleaseDate;
+
+/// A version section of RELEASES.md which was skipped, or read as unreleased, during parsing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseDiagnostic {
+    /// 1-based line number of the version heading
+    pub line: usize,
+    pub heading: String,
+    pub reason: String,
+}
+
+impl fmt::Display for ParseDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "RELEASES.md:{}: {} (in '{}')", self.line, self.reason, self.heading)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ParsedChangelogs {
+    pub catalog: ReleaseCatalog,
+    pub diagnostics: Vec<ParseDiagnostic>,
 }
 
 #[derive(Debug, Clone)]
 pub struct VersionManager {
     config: Config,
+    clock: Clock,
+    schedule: ReleaseSchedule,
+    editions: Editions,
+    versions: BTreeSet<Version>,
 }
 
 // We do this because of https://github.com/rust-lang/rust/commit/495d7ee587dc1b8d99fd9f0bce2f72b0072e3aca
 // So we'll be a bit permissive with such cases where the minor version is missing
-fn parse_lenient_version(version: &str) -> Option<Version> {
-    let splits = version.split('.').count();
+pub fn parse_lenient_version(version: &str) -> Option<Version> {
+    let core_len = version.find(['-', '+']).unwrap_or(version.len());
+    let (core, suffix) = version.split_at(core_len);
 
-    if splits < 3 {
-        return Version::parse(&format!("{version}.0")).ok()
+    if core.split('.').count() < 3 {
+        return Version::parse(&format!("{core}.0{suffix}")).ok()
     }
 
     Version::parse(version).ok()
@@ -30,77 +59,180 @@ fn parse_lenient_version(version: &str) -> Option<Version> {
 
 impl VersionManager {
     pub fn new(config: Config) -> Self {
-        Self { config }
+        let schedule = ReleaseSchedule::new(
+            config.epoch_version.clone(),
+            config.epoch_date,
+            &config.schedule_overrides,
+            &config.cycle_events,
+        );
+        let editions = Editions::new(&config.editions);
+        Self {
+            config,
+            clock: Clock::System,
+            schedule,
+            editions,
+            versions: BTreeSet::new(),
+        }
     }
 
-    pub fn calculate_release_date(&self, now_date: NaiveDate, incr: u32) -> ReleaseDate {
-        let new_releases = ((now_date - self.config.epoch_date).num_weeks() as f64 / 6.0).floor() as u32;
-        let release_date = self.config.epoch_date + Duration::weeks(((new_releases + incr) * 6).into());
-        let branch_date = self.config.epoch_date + Duration::weeks(((new_releases + incr - 1) * 6).into()) - Duration::days(6);
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.clock = clock;
+        self
+    }
 
-        ReleaseDate {
-            release_date,
-            branch_date,
-        }
+    pub fn clock(&self) -> &Clock {
+        &self.clock
     }
 
-    pub fn determine_weight(&self, version: &Version) -> u64 {
-        let base_weight = u64::MAX - ((version.major) << 24) - ((version.minor) << 8) - version.patch;
+    pub fn today(&self) -> NaiveDate {
+        self.clock.today()
+    }
 
-        if version.pre.is_empty() {
-            return base_weight
-        }
+    /// Makes the schedule use the actual release dates from RELEASES.md and adds the editions announced in
+    /// the release notes
+    pub fn set_known_releases(&mut self, catalog: &ReleaseCatalog) {
+        self.schedule.set_known_releases(catalog);
+        self.editions = Editions::resolve(&self.config.editions, catalog);
+        self.register_versions(catalog.versions());
+    }
 
-        // Very hacky, but works for now
-        let pre_hash = version.pre.to_string().chars().map(|c| c as u64).sum::<u64>() % 100;
-        base_weight.saturating_add(pre_hash)
+    /// Makes `versions` take part in [`Self::determine_weight`]. All versions which get a page should be
+    /// registered before any weight is determined.
+    pub fn register_versions<'a>(&mut self, versions: impl IntoIterator<Item = &'a Version>) {
+        self.versions.extend(versions.into_iter().cloned());
     }
 
-    pub fn parse_changelogs(&self, body: &str) -> HashMap<Version, (String, NaiveDate)> {
-        let split_re = RegexBuilder::new("^Version\\s+")
-            .multi_line(true)
-            .build()
-            .unwrap();
-            
-        split_re
-            .split(body)
-            .filter_map(|s| {
-                if let Some(ws_idx) = s.find(|c: char| c.is_whitespace()) {
-                    let rest = &s[ws_idx..];
-                    let version = &s[0..ws_idx];
-                    let time: NaiveDate = s[ws_idx + 1..].trim_start()[1..11].parse().unwrap();
-                    if let Some(version) = parse_lenient_version(version) {
-                        let changelog = rest.lines().skip(2).collect::<Vec<_>>().join("\n");
-                        Some((version, (changelog, time)))
-                    } else {
-                        panic!("Lenient version parsing failed for '{version}'");
-                    }
-                } else {
-                    None
-                }
-            })
-            .collect()
+    /// Release and branch dates of `version`, past or projected. A point release has the release date
+    /// from RELEASES.md and the branch date of its series. See [`ReleaseSchedule`] for where the dates
+    /// come from.
+    pub fn dates_for_version(&self, version: &Version) -> ReleaseDate {
+        self.schedule.dates(version)
+    }
+
+    pub fn editions(&self) -> &Editions {
+        &self.editions
+    }
+
+    /// The stable, beta and nightly versions on `date`, past or projected. `None` before Rust 1.0.
+    pub fn version_for_date(&self, date: NaiveDate) -> Option<(Version, Version, Version)> {
+        self.schedule.versions_on(date)
+    }
+
+    /// The dates of the `incr`-th minor release after the stable version on `now_date`, e.g. 1 for the
+    /// current beta. Overrides and the dates from RELEASES.md are taken into account like everywhere else.
+    /// `None` before Rust 1.0.
+    pub fn calculate_release_date(&self, now_date: NaiveDate, incr: u32) -> Option<ReleaseDate> {
+        let (stable, _, _) = self.schedule.versions_on(now_date)?;
+        Some(self.schedule.dates(&Version::new(stable.major, stable.minor + u64::from(incr), 0)))
     }
 
-    pub fn get_current_versions(&self, changelogs: &HashMap<Version, (String, NaiveDate)>) -> (Version, Version, Version) {
-        let stable_version = changelogs
-            .iter()
-            .filter(|(_, (_, release_date))| *release_date <= Utc::now().naive_utc().date())
-            .max_by_key(|(v, _)| *v)
-            .unwrap()
-            .0
-            .clone();
-            
-        let beta_version = stable_version.clone().tap_mut(|v| {
-            v.minor += 1;
-            v.patch = 0;
-        });
-        
-        let nightly_version = stable_version.clone().tap_mut(|v| {
-            v.minor += 2;
-            v.patch = 0;
-        });
-
-        (stable_version, beta_version, nightly_version)
+    /// Hugo sidebar weight, lower weights come first. The weight is derived from the number of registered
+    /// versions with a higher semver precedence, so registered versions are ordered exactly like `Version`'s
+    /// `Ord`, whatever their components or pre-release identifiers. An unregistered version is ordered
+    /// correctly against the registered ones.
+    pub fn determine_weight(&self, version: &Version) -> u64 {
+        let newer = self.versions.range((Bound::Excluded(version), Bound::Unbounded)).count() as u64;
+        2 * newer + u64::from(self.versions.contains(version))
+    }
+
+    pub fn parse_changelogs(&self, body: &str) -> Result<ParsedChangelogs> {
+        // Both setext (`Version 1.80.0 (2024-07-25)` followed by `===`) and ATX (`# Version 1.80.0`) headings
+        let heading_re = Regex::new(r"^\s{0,3}(#{1,6}\s+)?Version\s+(\S+)(.*?)[\s#]*$").unwrap();
+        let date_re = Regex::new(r"\d{4}-\d{2}-\d{2}").unwrap();
+        let underline_re = Regex::new(r"^\s{0,3}(=+|-{3,})\s*$").unwrap();
+        let fence_re = Regex::new(r"^\s*(```|~~~)").unwrap();
+
+        let lines: Vec<&str> = body.lines().collect();
+        let mut headings = Vec::new();
+        let mut fence = None;
+        for (idx, line) in lines.iter().enumerate() {
+            if let Some(caps) = fence_re.captures(line) {
+                match fence {
+                    Some(open) if open == caps[1] => fence = None,
+                    Some(_) => {}
+                    None => fence = Some(caps[1].to_string()),
+                }
+                continue;
+            }
+            let Some(caps) = heading_re.captures(line).filter(|_| fence.is_none()) else {
+                continue;
+            };
+
+            // A paragraph like "Version 2 of the resolver is now the default." isn't a heading
+            let is_atx = caps.get(1).is_some();
+            let has_date = caps[3].trim().starts_with('(');
+            let is_setext = lines[idx + 1..]
+                .iter()
+                .find(|line| !line.trim().is_empty())
+                .is_some_and(|line| underline_re.is_match(line));
+            if is_atx || has_date || is_setext {
+                headings.push(idx);
+            }
+        }
+
+        if headings.is_empty() {
+            bail!("no version headings found in RELEASES.md");
+        }
+
+        let mut parsed = ParsedChangelogs::default();
+
+        for (pos, &start) in headings.iter().enumerate() {
+            let end = headings.get(pos + 1).copied().unwrap_or(lines.len());
+            let heading = lines[start].trim();
+            let caps = heading_re.captures(heading).unwrap();
+            let diagnostic = |reason: String| ParseDiagnostic {
+                line: start + 1,
+                heading: heading.to_string(),
+                reason,
+            };
+
+            let Some(version) = parse_lenient_version(&caps[2]) else {
+                parsed.diagnostics.push(diagnostic(format!("unparsable version '{}', section skipped", &caps[2])));
+                continue;
+            };
+
+            let date_text = caps[3].trim();
+            let release_date = if date_text.is_empty() {
+                None
+            } else if let Some(date) = date_re
+                .find(date_text)
+                .and_then(|m| m.as_str().parse::<NaiveDate>().ok())
+            {
+                Some(date)
+            } else if date_text.starts_with('(') {
+                // E.g. `(TBD)` on master or beta
+                parsed.diagnostics.push(diagnostic(format!("no release date in '{date_text}', read as unreleased")));
+                None
+            } else {
+                parsed.diagnostics.push(diagnostic(format!("invalid release date '{date_text}', section skipped")));
+                continue;
+            };
+
+            if parsed.catalog.contains(&version) {
+                parsed.diagnostics.push(diagnostic(format!("duplicate section for {version}, skipped")));
+                continue;
+            }
+
+            let mut body_start = start + 1;
+            while lines[body_start..end].first().is_some_and(|line| line.trim().is_empty()) {
+                body_start += 1;
+            }
+            if lines[body_start..end].first().is_some_and(|line| underline_re.is_match(line)) {
+                body_start += 1;
+            }
+            let changelog = lines[body_start..end].join("\n");
+
+            parsed.catalog.insert(ReleaseEntry {
+                version,
+                notes: ReleaseNotes::parse(&changelog),
+                release_date,
+            });
+        }
+
+        if parsed.catalog.is_empty() {
+            bail!("none of the {} version sections in RELEASES.md could be parsed", headings.len());
+        }
+
+        Ok(parsed)
     }
 }
@@ -0,0 +1,67 @@
+use rust_changelogs::cassette::CassetteMode;
+use rust_changelogs::release_notes_source::DEFAULT_RELEASES_URL;
+use rust_changelogs::{Cassette, Config, GitHubApi, GitHubClient, HttpClient, ReleaseNotesSource};
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+
+const CASSETTE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/cassette");
+
+#[tokio::test]
+async fn replay() {
+    let config = Config {
+        cassette: Some(Cassette::new(CassetteMode::Replay, CASSETTE)),
+        ..Config::new()
+    };
+    let github = GitHubClient::new(config.clone());
+
+    // The second page is requested through the `Link` header of the first one
+    let issues = github.fetch_milestone_issues(111).await.unwrap();
+    assert_eq!(issues.iter().map(|i| i.number).collect::<Vec<_>>(), [127679, 128250]);
+
+    let releases = ReleaseNotesSource::Url(DEFAULT_RELEASES_URL.to_string())
+        .fetch_with(&HttpClient::new(&config))
+        .await
+        .unwrap();
+    assert!(releases.starts_with("Version 1.80.1 (2024-08-08)\n"));
+
+    let error = github.fetch_milestone_issues(112).await.unwrap_err();
+    assert!(format!("{error:#}").contains("no recorded response for GET https://api.github.com/repos/rust-lang/rust/issues?"));
+}
+
+#[tokio::test]
+async fn record_then_replay() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/RELEASES.md", listener.local_addr().unwrap());
+    std::thread::spawn(move || {
+        for (n, stream) in listener.incoming().take(2).enumerate() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 2 {
+                line.clear();
+            }
+            let body = format!("response {n}");
+            write!(stream, "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}", body.len()).unwrap();
+        }
+    });
+
+    let dir = std::env::temp_dir().join(format!("cassette-test-{}", std::process::id()));
+    // Only the files of a previous recording are replaced
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("00001-get-stale.json"), "{}").unwrap();
+    std::fs::write(dir.join("settings.json"), "{}").unwrap();
+
+    let recorder = Cassette::new(CassetteMode::Record, &dir);
+    assert_eq!(recorder.get_text(&url).await.unwrap(), "response 0");
+    assert_eq!(recorder.get_text(&url).await.unwrap(), "response 1");
+    assert!(dir.join("settings.json").exists());
+    std::fs::remove_file(dir.join("settings.json")).unwrap();
+
+    // Repeated requests replay in order, then stick to the last response
+    let player = Cassette::new(CassetteMode::Replay, &dir);
+    assert_eq!(player.get_text(&url).await.unwrap(), "response 0");
+    assert_eq!(player.get_text(&url).await.unwrap(), "response 1");
+    assert_eq!(player.get_text(&url).await.unwrap(), "response 1");
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,266 @@
+use chrono::NaiveDate;
+use rust_changelogs::{ChangelogGenerator, Clock, Config, VersionManager};
+use semver::Version;
+use std::collections::{HashMap, HashSet};
+
+const RELEASES: &str = include_str!("fixtures/RELEASES.md");
+
+#[test]
+fn upgrade_checklist() {
+    let version_manager = VersionManager::new(Config::new());
+    let catalog = version_manager.parse_changelogs(RELEASES).unwrap().catalog;
+    let changelog_generator = ChangelogGenerator::new(version_manager);
+
+    let checklist = changelog_generator.generate_upgrade_checklist(
+        &catalog,
+        &Version::parse("1.56.0").unwrap(),
+        &Version::parse("1.80.1").unwrap(),
+    );
+
+    assert_eq!(
+        checklist,
+        "# Upgrading from Rust 1.56.0 to 1.80.1
+
+## 1.80.0
+
+### Compatibility Notes
+
+- [ ] [Rustdoc lints are now checked in the `rustdoc::` tool namespace](https://github.com/rust-lang/rust/pull/124577/)
+- [ ] [Update the minimum external LLVM to 17.](https://github.com/rust-lang/rust/pull/122649/)
+- [ ] The `box_pointers` lint has been removed. See [#126018] for more details.
+
+### Cargo
+
+- [ ] [Stabilize `-Zcheck-cfg` as always enabled](https://github.com/rust-lang/cargo/pull/13571/)
+
+[#126018]: https://github.com/rust-lang/rust/issues/126018
+"
+    );
+}
+
+#[test]
+fn compatibility_notes_page() {
+    let version_manager = VersionManager::new(Config::new());
+    let catalog = version_manager.parse_changelogs(RELEASES).unwrap().catalog;
+    let changelog_generator = ChangelogGenerator::new(version_manager);
+
+    let content = changelog_generator.generate_compatibility_notes_content(&catalog);
+    let headings: Vec<_> = content.lines().filter(|l| l.starts_with("## ")).collect();
+
+    assert_eq!(
+        headings,
+        [
+            "## [1.85.0](/docs/1.85.0)",
+            "## [1.80.0](/docs/1.80.0)",
+            "## [1.56.0](/docs/1.56.0)",
+            "## [1.31.0](/docs/1.31.0)",
+        ]
+    );
+    assert!(content.contains("[cargo/9375]: https://github.com/rust-lang/cargo/pull/9375/\n"));
+    assert!(!content.contains("[85305]:"));
+}
+
+#[test]
+fn patch_releases_in_minor_page() {
+    let version_manager = VersionManager::new(Config::new());
+    let catalog = version_manager.parse_changelogs(RELEASES).unwrap().catalog;
+    let changelog_generator = ChangelogGenerator::new(version_manager);
+
+    let series: Vec<_> = catalog.minor_series(1, 80).collect();
+    let minor = series[0];
+    let content = changelog_generator.generate_released_version_content(
+        &minor.version,
+        &minor.notes,
+        &minor.release_date.unwrap(),
+        &series[1..],
+    );
+
+    assert!(content.contains(
+        "  - [1.80.1](/docs/1.80.1), _8 August, 2024_: Fix miscompilation in the jump threading MIR optimization when comparing floats (and 1 more fix)\n"
+    ));
+
+    let content = changelog_generator.generate_series_content(&series);
+    let headings: Vec<_> = content.lines().filter(|l| l.starts_with("## ")).collect();
+    assert_eq!(headings, ["## 1.80.1", "## 1.80.0"]);
+}
+
+#[test]
+fn series_link_label_collisions() {
+    let version_manager = VersionManager::new(Config::new());
+    let body = "Version 1.80.1 (2024-08-08)
+===========================
+
+- [Fix the jump threading miscompilation.][fix]
+- [Same link][shared]
+
+[fix]: https://github.com/rust-lang/rust/pull/128271
+[shared]: https://example.com/shared
+
+Version 1.80.0 (2024-07-25)
+==========================
+
+- [Stabilize `LazyCell`.][fix] See the [fix] and [the docs][].
+- [Inline](https://example.com/inline) [Same link][shared]
+
+[fix]: https://github.com/rust-lang/rust/pull/121377
+[the docs]: https://doc.rust-lang.org/std/cell/struct.LazyCell.html
+[shared]: https://example.com/shared
+";
+    let catalog = version_manager.parse_changelogs(body).unwrap().catalog;
+    let changelog_generator = ChangelogGenerator::new(version_manager);
+
+    let series: Vec<_> = catalog.minor_series(1, 80).collect();
+    let content = changelog_generator.generate_series_content(&series);
+
+    assert!(content.contains("- [Fix the jump threading miscompilation.][fix]\n"));
+    assert!(content.contains("- [Stabilize `LazyCell`.][fix-1.80.0] See the [fix][fix-1.80.0] and [the docs][].\n"));
+    assert!(content.contains("- [Inline](https://example.com/inline) [Same link][shared]\n"));
+    assert!(content.contains("[fix]: https://github.com/rust-lang/rust/pull/128271\n"));
+    assert!(content.contains("[fix-1.80.0]: https://github.com/rust-lang/rust/pull/121377\n"));
+    assert_eq!(content.matches("[shared]: ").count(), 1);
+}
+
+#[test]
+fn unreleased_hint_lists_upcoming_events() {
+    let clock = Clock::fixed_date(NaiveDate::from_ymd_opt(2024, 9, 1).unwrap());
+    let mut version_manager = VersionManager::new(Config::new()).with_clock(clock);
+    let catalog = version_manager.parse_changelogs(RELEASES).unwrap().catalog;
+    version_manager.set_known_releases(&catalog);
+    let changelog_generator = ChangelogGenerator::new(version_manager);
+
+    let content = changelog_generator.generate_unreleased_version_content(&Version::new(1, 81, 0), 111, &Version::new(1, 80, 0), &[]);
+
+    // Past events are left out, the freeze is still going on
+    assert!(!content.contains("Promoted to beta"));
+    assert!(content.contains("- Beta backport freeze: _29 August, 2024_ to _4 September, 2024_\n"));
+    assert!(content.contains("- Release blog post: _5 September, 2024_\n"));
+}
+
+#[test]
+fn index_with_fixed_clock() {
+    let clock = Clock::fixed_date(NaiveDate::from_ymd_opt(2024, 8, 1).unwrap());
+    let mut version_manager = VersionManager::new(Config::new()).with_clock(clock);
+    let catalog = version_manager.parse_changelogs(RELEASES).unwrap().catalog;
+    version_manager.set_known_releases(&catalog);
+    let changelog_generator = ChangelogGenerator::new(version_manager);
+
+    let (stable, beta, nightly) = catalog.current_versions(clock.today()).unwrap();
+    let unreleased = HashSet::from([&beta, &nightly]);
+    let index = changelog_generator.generate_index_content(&stable, &beta, &nightly, &unreleased, None, HashMap::new());
+
+    assert_eq!(
+        index,
+        "---
+title: Rust Versions
+type: docs
+---
+
+## Rust Versions
+
+- Stable: [1.80.0](/docs/1.80.0)
+- Beta: [1.81.0](/docs/1.81.0) (5 September, 2024, 35 days left)
+  - Beta backport freeze: _29 August, 2024_ to _4 September, 2024_
+  - Pre-release testing on dev-static: _3 September, 2024_
+  - Release blog post: _5 September, 2024_
+- Nightly: [1.82.0](/docs/1.82.0) (17 October, 2024, 77 days left)
+  - Promoted to beta: _5 September, 2024_
+  - Beta backport freeze: _10 October, 2024_ to _16 October, 2024_
+  - Pre-release testing on dev-static: _15 October, 2024_
+  - Release blog post: _17 October, 2024_
+
+## Reference
+
+- [Stabilized APIs](/stabilized-apis/)
+- [Compatibility Notes](/compatibility-notes/)
+- [Editions](/editions/)
+
+
+## Ongoing Stabilization PRs
+
+
+
+## About releases.rs
+
+- [Github Repo](https://github.com/releases-rs/releases-rs/)
+- Generated at <span class=\"utc-timestamp\" data-utc=\"2024-08-01T00:00:00+00:00\">...</span>
+
+"
+    );
+}
+
+#[test]
+fn editions() {
+    let mut version_manager = VersionManager::new(Config { editions: Vec::new(), ..Config::new() });
+    let catalog = version_manager.parse_changelogs(RELEASES).unwrap().catalog;
+    version_manager.set_known_releases(&catalog);
+
+    let detected: Vec<_> = version_manager.editions().iter().map(|e| (e.year, e.version.to_string())).collect();
+    assert_eq!(
+        detected,
+        [(2018, "1.31.0".to_string()), (2021, "1.56.0".to_string()), (2024, "1.85.0".to_string())]
+    );
+
+    let changelog_generator = ChangelogGenerator::new(version_manager);
+    let entry = catalog.get(&Version::parse("1.85.0").unwrap()).unwrap();
+    let content = changelog_generator.generate_released_version_content(
+        &entry.version,
+        &entry.notes,
+        &entry.release_date.unwrap(),
+        &[],
+    );
+    assert!(content.contains("- 🎉 Stabilizes the [Rust 2024 edition](/editions/#rust-2024)\n{{% /hint %}}"));
+
+    let content = changelog_generator.generate_editions_content(&catalog);
+    let headings: Vec<_> = content.lines().filter(|l| l.starts_with("## ") || l.starts_with("### ")).collect();
+    assert_eq!(
+        headings,
+        [
+            "## Rust 2024",
+            "### [1.85.0](/docs/1.85.0)",
+            "## Rust 2021",
+            "### [1.56.0](/docs/1.56.0)",
+            "## Rust 2018",
+            "### [1.31.0](/docs/1.31.0)",
+        ]
+    );
+    assert!(content.contains("- [`unsafe_op_in_unsafe_fn` lint now warns in the 2024 edition.]"));
+    assert!(content.contains("[rust-2021-edition-guide]: https://doc.rust-lang.org/nightly/edition-guide/rust-2021/index.html\n"));
+}
+
+#[test]
+fn calendar() {
+    let clock = Clock::fixed_date(NaiveDate::from_ymd_opt(2024, 8, 1).unwrap());
+    let mut version_manager = VersionManager::new(Config::new()).with_clock(clock);
+    let catalog = version_manager.parse_changelogs(RELEASES).unwrap().catalog;
+    version_manager.set_known_releases(&catalog);
+    let changelog_generator = ChangelogGenerator::new(version_manager);
+
+    let upcoming = [Version::new(1, 81, 0), Version::new(1, 82, 0)];
+    let calendar = changelog_generator.generate_calendar(&catalog, &upcoming, "https://releases.rs/");
+
+    assert!(calendar.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+    assert!(calendar.ends_with("END:VEVENT\r\nEND:VCALENDAR\r\n"));
+    assert!(calendar.lines().all(|line| line.len() <= 76));
+
+    let summaries: Vec<_> = calendar.lines().filter_map(|l| l.strip_prefix("SUMMARY:")).map(str::trim_end).collect();
+    assert_eq!(
+        summaries,
+        [
+            "Rust 0.12.0 released",
+            "Rust 1.0.0-alpha released",
+            "Rust 1.0.0-alpha.2 released",
+            "Rust 1.31.0 released",
+            "Rust 1.56.0 released",
+            "Rust 1.80.0 released",
+            "Rust 1.81.0 branches to beta",
+            "Rust 1.81.0 release",
+            "Rust 1.82.0 branches to beta",
+            "Rust 1.82.0 release",
+        ]
+    );
+    assert!(calendar.contains(
+        "BEGIN:VEVENT\r\nUID:release-1.81.0@releases.rs\r\nDTSTAMP:20240801T000000Z\r\n\
+         DTSTART;VALUE=DATE:20240905\r\nDTEND;VALUE=DATE:20240906\r\n"
+    ));
+    assert!(calendar.contains("URL:https://releases.rs/docs/1.81.0/\r\n"));
+}
@@ -0,0 +1,33 @@
+use chrono::NaiveDate;
+use rust_changelogs::channel_manifest::{ChannelManifest, ChannelManifestSource, RustupChannel};
+use semver::Version;
+
+#[tokio::test]
+async fn reads_manifests_from_directory() {
+    let source = ChannelManifestSource::Directory(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/manifests").into());
+    let channels = source.fetch_all().await.unwrap();
+
+    assert_eq!(
+        channels.stable,
+        ChannelManifest {
+            channel: RustupChannel::Stable,
+            version: Version::new(1, 80, 0),
+            date: NaiveDate::from_ymd_opt(2024, 7, 25).unwrap(),
+            commit_hash: Some("051478957371ee0084a7c0913941d2a8c4757bb9".to_string()),
+        }
+    );
+    assert_eq!(channels.beta.version, Version::parse("1.80.0-beta.6").unwrap());
+    assert_eq!(channels.nightly.version, Version::parse("1.82.0-nightly").unwrap());
+
+    // Release day, stable is out but beta is still on 1.80
+    assert!(channels.beta_pending_bump());
+    assert_eq!(
+        channels.current_versions(),
+        (Version::new(1, 80, 0), Version::new(1, 81, 0), Version::new(1, 82, 0))
+    );
+}
+
+#[test]
+fn rejects_incomplete_manifest() {
+    assert!(ChannelManifest::parse(RustupChannel::Stable, "date = \"2024-07-25\"\n[pkg.cargo]\nversion = \"1.80.0\"").is_err());
+}
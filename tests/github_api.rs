@@ -0,0 +1,86 @@
+use anyhow::{bail, Result};
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use octocrab::models::issues::Issue;
+use octocrab::models::{IssueId, Milestone};
+use rust_changelogs::github_api::fetch_milestones_issues;
+use rust_changelogs::{ChangelogGenerator, Clock, Config, GitHubApi, InMemoryGitHub, VersionManager};
+use semver::Version;
+use std::collections::HashMap;
+
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/github");
+
+#[tokio::test]
+async fn fixtures() {
+    let github = InMemoryGitHub::from_dir(FIXTURES).unwrap();
+
+    let milestones = github.fetch_milestones().await.unwrap();
+    let mut versions: Vec<_> = milestones.keys().map(Version::to_string).collect();
+    versions.sort();
+    assert_eq!(versions, ["1.81.0", "1.82.0"]);
+
+    let issues = github.fetch_milestone_issues(111).await.unwrap();
+    assert_eq!(issues.iter().map(|i| i.number).collect::<Vec<_>>(), [127679, 128250]);
+    assert!(github.fetch_milestone_issues(1).await.unwrap().is_empty());
+    assert_eq!(github.fetch_stabilization_prs().await.unwrap().len(), 1);
+
+    let github = github.with_cutoff(Utc.with_ymd_and_hms(2024, 7, 20, 0, 0, 0).unwrap());
+    assert_eq!(github.fetch_milestones().await.unwrap().len(), 2);
+    assert_eq!(github.fetch_milestone_issues(111).await.unwrap().len(), 0);
+    assert_eq!(github.fetch_milestone_issues(110).await.unwrap().len(), 2);
+    assert!(github.fetch_stabilization_prs().await.unwrap().is_empty());
+
+    assert!(InMemoryGitHub::from_dir("does/not/exist").unwrap().fetch_milestones().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn unreleased_page_offline() {
+    let github = InMemoryGitHub::from_dir(FIXTURES).unwrap();
+    let clock = Clock::fixed_date(NaiveDate::from_ymd_opt(2024, 8, 5).unwrap());
+    let changelog_generator = ChangelogGenerator::new(VersionManager::new(Config::new()).with_clock(clock));
+
+    let milestones = github.fetch_milestones().await.unwrap();
+    let nightly = Version::new(1, 82, 0);
+    let issues = github.fetch_milestone_issues(milestones[&nightly].number).await.unwrap();
+    let content =
+        changelog_generator.generate_unreleased_version_content(&nightly, 111, &Version::new(1, 80, 0), &issues);
+
+    assert!(content.contains(
+        "- [Stabilize unsafe extern blocks](https://github.com/rust-lang/rust/pull/128250) _(merged 2 days ago)_\n"
+    ));
+    assert!(content.contains("- [Stabilize `raw_ref_op`](https://github.com/rust-lang/rust/pull/127679) _(merged 6 days ago)_\n"));
+}
+
+/// The fixtures, except that milestone 110 can't be fetched
+struct FailingMilestone(InMemoryGitHub);
+
+impl GitHubApi for FailingMilestone {
+    async fn fetch_milestones(&self) -> Result<HashMap<Version, Milestone>> {
+        self.0.fetch_milestones().await
+    }
+
+    async fn fetch_stabilization_prs(&self) -> Result<HashMap<IssueId, Issue>> {
+        self.0.fetch_stabilization_prs().await
+    }
+
+    async fn fetch_milestone_issues(&self, milestone_id: i64) -> Result<Vec<Issue>> {
+        if milestone_id == 110 {
+            bail!("server error");
+        }
+        self.0.fetch_milestone_issues(milestone_id).await
+    }
+
+    fn with_cutoff(self, cutoff: DateTime<Utc>) -> Self {
+        Self(self.0.with_cutoff(cutoff))
+    }
+}
+
+#[tokio::test]
+async fn concurrent_milestone_issues() {
+    let github = FailingMilestone(InMemoryGitHub::from_dir(FIXTURES).unwrap());
+
+    let issues = fetch_milestones_issues(&github, [111, 110, 1], 2).await;
+    assert_eq!(issues.keys().copied().collect::<Vec<_>>(), [1, 110, 111]);
+    assert!(issues[&1].as_ref().unwrap().is_empty());
+    assert_eq!(issues[&110].as_ref().unwrap_err().to_string(), "server error");
+    assert_eq!(issues[&111].as_ref().unwrap().iter().map(|i| i.number).collect::<Vec<_>>(), [127679, 128250]);
+}
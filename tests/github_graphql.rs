@@ -0,0 +1,271 @@
+use rust_changelogs::cassette::Exchange;
+use rust_changelogs::{Cassette, Config, GitHubApi, GitHubClient, GitHubGraphQlClient};
+use serde_json::{json, Value};
+
+fn pull_request(number: u64, title: &str, merged_at: Option<&str>) -> Value {
+    json!({
+        "__typename": "PullRequest",
+        "databaseId": number * 10,
+        "id": format!("PR_{number}"),
+        "number": number,
+        "title": title,
+        "body": null,
+        "url": format!("https://github.com/rust-lang/rust/pull/{number}"),
+        "state": if merged_at.is_some() { "MERGED" } else { "OPEN" },
+        "locked": false,
+        "authorAssociation": "CONTRIBUTOR",
+        "createdAt": "2024-07-13T12:00:00Z",
+        "updatedAt": "2024-07-30T18:00:00Z",
+        "closedAt": merged_at,
+        "mergedAt": merged_at,
+        "author": {
+            "__typename": "User",
+            "login": "rust-contributor",
+            "url": "https://github.com/rust-contributor",
+            "avatarUrl": "https://avatars.githubusercontent.com/u/1000001?v=4",
+            "databaseId": 1000001
+        },
+        "labels": connection(vec![json!({ "id": "LA_1", "name": "relnotes", "description": null, "color": "fad8c7", "isDefault": false })], None),
+        "comments": { "totalCount": 3 }
+    })
+}
+
+fn connection(nodes: Vec<Value>, end_cursor: Option<&str>) -> Value {
+    json!({ "pageInfo": { "hasNextPage": end_cursor.is_some(), "endCursor": end_cursor }, "nodes": nodes })
+}
+
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/github");
+
+const MILESTONE: &str = r#"{
+    "id": "MI_111", "number": 111, "title": "1.82.0", "state": "OPEN", "description": null,
+    "url": "https://github.com/rust-lang/rust/milestone/111", "createdAt": "2024-07-19T10:00:00Z",
+    "updatedAt": null, "closedAt": null, "dueOn": null
+}"#;
+
+/// A milestone as listed by the milestones query, with the counts of its closed relnotes PRs and issues
+fn milestone_summary(milestone: &Value, pull_requests: u32, issues: u32) -> Value {
+    let mut milestone = milestone.clone();
+    milestone["relnotesPullRequests"] = json!({ "totalCount": pull_requests });
+    milestone["relnotesIssues"] = json!({ "totalCount": issues });
+    milestone
+}
+
+fn milestones(nodes: Vec<Value>) -> Value {
+    json!({ "data": { "repository": { "milestones": connection(nodes, None) } } })
+}
+
+/// A page of the milestone query, `issues` is `None` when the connection wasn't requested
+fn milestone_items(milestone: &Value, pull_requests: Value, issues: Option<Value>) -> Value {
+    let mut milestone = milestone.clone();
+    milestone["pullRequests"] = pull_requests;
+    if let Some(issues) = issues {
+        milestone["issues"] = issues;
+    }
+    json!({ "data": { "repository": { "milestone": milestone } } })
+}
+
+/// Serves `responses` to the GraphQL endpoint in order
+fn client(responses: Vec<Value>) -> GitHubGraphQlClient {
+    let cassette = Cassette::replaying(responses.into_iter().map(|body| Exchange {
+        method: "POST".to_string(),
+        url: "https://api.github.com/graphql".to_string(),
+        status: 200,
+        headers: vec![("x-ratelimit-resource".to_string(), "graphql".to_string())],
+        body: body.to_string(),
+    }));
+
+    GitHubGraphQlClient::new(Config { cassette: Some(cassette), ..Config::new() })
+}
+
+#[tokio::test]
+async fn graphql_backend() {
+    let milestone: Value = serde_json::from_str(MILESTONE).unwrap();
+    let github = client(
+        vec![
+            milestones(vec![milestone_summary(&milestone, 2, 0)]),
+            // The PRs have a second page, the issues don't
+            milestone_items(
+                &milestone,
+                connection(vec![pull_request(128250, "Stabilize unsafe extern blocks", Some("2024-08-03T12:00:00Z"))], Some("c1")),
+                Some(connection(vec![], None)),
+            ),
+            milestone_items(
+                &milestone,
+                connection(vec![pull_request(127679, "Stabilize `raw_ref_op`", Some("2024-07-30T18:00:00Z"))], None),
+                None,
+            ),
+            json!({ "data": {
+                "s0": connection(vec![pull_request(128316, "Stabilise `const_float_methods`", None)], None),
+                "s1": connection(vec![pull_request(128400, "Tracking issue: stabilize `foo`", None)], None),
+                "s2": connection(vec![], None),
+                "s3": connection(vec![], None)
+            } }),
+            json!({ "errors": [{ "message": "Could not resolve to a Repository" }] }),
+        ],
+    );
+
+    let milestones = github.fetch_milestones().await.unwrap();
+    let milestone = &milestones[&semver::Version::new(1, 82, 0)];
+    assert_eq!((milestone.number, milestone.state.as_deref()), (111, Some("open")));
+
+    let issues = github.fetch_milestone_issues(111).await.unwrap();
+    assert_eq!(issues.iter().map(|i| i.number).collect::<Vec<_>>(), [128250, 127679]);
+    assert_eq!(issues[1].closed_at.unwrap().to_rfc3339(), "2024-07-30T18:00:00+00:00");
+    assert_eq!(issues[1].html_url.as_str(), "https://github.com/rust-lang/rust/pull/127679");
+    assert_eq!(issues[1].user.login, "rust-contributor");
+    assert_eq!(issues[1].labels[0].name, "relnotes");
+    assert_eq!(issues[1].milestone.as_ref().unwrap().title, "1.82.0");
+    assert!(issues[1].pull_request.is_some());
+
+    // Titles which only mention a term are left out
+    let prs = github.fetch_stabilization_prs().await.unwrap();
+    assert_eq!(prs.values().map(|pr| pr.number).collect::<Vec<_>>(), [128316]);
+
+    let error = github.fetch_milestones().await.unwrap_err();
+    assert_eq!(error.to_string(), "GraphQL query failed: Could not resolve to a Repository");
+}
+
+fn read_fixture(name: &str) -> Value {
+    serde_json::from_str(&std::fs::read_to_string(format!("{FIXTURES}/{name}")).unwrap()).unwrap()
+}
+
+/// A REST issue of the fixtures as a GraphQL node, where PRs are merged when they were closed
+fn graphql_item(issue: &Value) -> Value {
+    let is_pull_request = !issue["pull_request"].is_null();
+    json!({
+        "__typename": if is_pull_request { "PullRequest" } else { "Issue" },
+        "databaseId": issue["id"],
+        "id": issue["node_id"],
+        "number": issue["number"],
+        "title": issue["title"],
+        "body": issue["body"],
+        "url": issue["html_url"],
+        "state": match (issue["state"].as_str(), is_pull_request) {
+            (Some("open"), _) => "OPEN",
+            (_, true) => "MERGED",
+            _ => "CLOSED",
+        },
+        "locked": issue["locked"],
+        "authorAssociation": issue["author_association"],
+        "createdAt": issue["created_at"],
+        "updatedAt": issue["updated_at"],
+        "closedAt": issue["closed_at"],
+        "mergedAt": if is_pull_request { issue["closed_at"].clone() } else { Value::Null },
+        "author": {
+            "__typename": issue["user"]["type"],
+            "login": issue["user"]["login"],
+            "url": issue["user"]["html_url"],
+            "avatarUrl": issue["user"]["avatar_url"],
+            "databaseId": issue["user"]["id"]
+        },
+        "labels": connection(issue["labels"].as_array().unwrap().iter().map(|label| json!({
+            "id": label["node_id"], "name": label["name"], "description": label["description"],
+            "color": label["color"], "isDefault": label["default"]
+        })).collect(), None),
+        "comments": { "totalCount": issue["comments"] }
+    })
+}
+
+fn graphql_milestone(milestone: &Value) -> Value {
+    json!({
+        "id": milestone["node_id"], "number": milestone["number"], "title": milestone["title"],
+        "state": milestone["state"].as_str().unwrap().to_uppercase(), "description": milestone["description"],
+        "url": milestone["html_url"], "createdAt": milestone["created_at"], "updatedAt": milestone["updated_at"],
+        "closedAt": milestone["closed_at"], "dueOn": milestone["due_on"]
+    })
+}
+
+/// The fields of an issue which the site uses, the GraphQL backend has no ids for milestones and labels
+fn comparable(value: impl serde::Serialize) -> Value {
+    let value = serde_json::to_value(value).unwrap();
+    json!({
+        "number": value["number"],
+        "title": value["title"],
+        "state": value["state"],
+        "html_url": value["html_url"],
+        "created_at": value["created_at"],
+        "closed_at": value["closed_at"],
+        "user": value["user"]["login"],
+        "labels": value["labels"].as_array().map(|labels| labels.iter().map(|label| label["name"].clone()).collect::<Vec<_>>()),
+        "milestone": value["milestone"]["title"],
+        "pull_request": value["pull_request"]["html_url"],
+        "comments": value["comments"],
+    })
+}
+
+/// The REST and GraphQL backends read the same data from the fixtures
+#[tokio::test]
+async fn graphql_matches_rest() {
+    let milestone_fixtures = read_fixture("milestones.json");
+    let milestone_fixtures = milestone_fixtures.as_array().unwrap();
+    let milestone = |number: i64| graphql_milestone(milestone_fixtures.iter().find(|m| m["number"] == number).unwrap());
+    let issues: Vec<(i64, Value)> = milestone_fixtures
+        .iter()
+        .map(|m| (m["number"].as_i64().unwrap(), read_fixture(&format!("issues/{}.json", m["number"]))))
+        .collect();
+
+    let issues_url = "https://api.github.com/repos/rust-lang/rust/issues?state=closed";
+    let rest_exchange = |url: String, body: Value| Exchange {
+        method: "GET".to_string(),
+        url,
+        status: 200,
+        headers: Vec::new(),
+        body: body.to_string(),
+    };
+    let mut all_issues: Vec<_> = issues.iter().flat_map(|(_, issues)| issues.as_array().unwrap().clone()).collect();
+    all_issues.sort_by_key(|issue| std::cmp::Reverse(issue["created_at"].as_str().unwrap().to_string()));
+    let rest = GitHubClient::new(Config {
+        cassette: Some(Cassette::replaying(
+            std::iter::once(rest_exchange(
+                format!("{issues_url}&labels=relnotes&sort=created&direction=desc&per_page=255"),
+                Value::Array(all_issues),
+            ))
+            .chain(issues.iter().map(|(number, issues)| {
+                rest_exchange(
+                    format!("{issues_url}&milestone={number}&labels=relnotes&sort=created&direction=asc&per_page=255"),
+                    issues.clone(),
+                )
+            })),
+        )),
+        ..Config::new()
+    });
+
+    let graphql = client(
+        std::iter::once(milestones(
+            issues
+                .iter()
+                .rev()
+                .map(|(number, issues)| milestone_summary(&milestone(*number), issues.as_array().unwrap().len() as u32, 0))
+                .collect(),
+        ))
+        .chain(issues.iter().map(|(number, issues)| {
+            milestone_items(
+                &milestone(*number),
+                connection(issues.as_array().unwrap().iter().map(graphql_item).collect(), None),
+                Some(connection(vec![], None)),
+            )
+        }))
+        .collect(),
+    );
+
+    let (rest_milestones, graphql_milestones) = (rest.fetch_milestones().await.unwrap(), graphql.fetch_milestones().await.unwrap());
+    assert_eq!(rest_milestones.len(), 2);
+    for (version, expected) in &rest_milestones {
+        let actual = &graphql_milestones[version];
+        assert_eq!(
+            (actual.number, &actual.title, &actual.state, &actual.html_url, actual.created_at),
+            (expected.number, &expected.title, &expected.state, &expected.html_url, expected.created_at)
+        );
+    }
+    assert_eq!(graphql_milestones.len(), rest_milestones.len());
+
+    for (number, _) in &issues {
+        let rest_issues = rest.fetch_milestone_issues(*number).await.unwrap();
+        let graphql_issues = graphql.fetch_milestone_issues(*number).await.unwrap();
+        assert_eq!(rest_issues.len(), 2);
+        assert_eq!(
+            graphql_issues.iter().map(comparable).collect::<Vec<_>>(),
+            rest_issues.iter().map(comparable).collect::<Vec<_>>()
+        );
+    }
+}
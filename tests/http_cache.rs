@@ -0,0 +1,86 @@
+use chrono::Duration;
+use rust_changelogs::http_cache::HttpCache;
+use rust_changelogs::{Config, HttpClient, ReleaseNotesSource};
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::sync::mpsc;
+
+/// Serves `ETag: "v1"` with a body counting the full responses, and a 304 when revalidated with that tag.
+/// Sends whether each request was conditional.
+fn serve(requests: usize) -> (String, mpsc::Receiver<bool>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/RELEASES.md", listener.local_addr().unwrap());
+    let (sender, receiver) = mpsc::channel();
+
+    std::thread::spawn(move || {
+        let mut downloads = 0;
+        for stream in listener.incoming().take(requests) {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut conditional = false;
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 2 {
+                conditional |= line.to_lowercase().starts_with("if-none-match: \"v1\"");
+                line.clear();
+            }
+            sender.send(conditional).unwrap();
+
+            if conditional {
+                write!(stream, "HTTP/1.1 304 Not Modified\r\nETag: \"v1\"\r\nConnection: close\r\n\r\n").unwrap();
+            } else {
+                downloads += 1;
+                let body = format!("download {downloads}");
+                write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nETag: \"v1\"\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                )
+                .unwrap();
+            }
+        }
+    });
+
+    (url, receiver)
+}
+
+fn client(cache: HttpCache) -> HttpClient {
+    HttpClient::new(&Config {
+        cassette: None,
+        http_cache: Some(cache),
+        ..Config::new()
+    })
+}
+
+#[tokio::test]
+async fn revalidation() {
+    let (url, conditional) = serve(3);
+    let dir = std::env::temp_dir().join(format!("http-cache-test-{}", std::process::id()));
+    let source = ReleaseNotesSource::Url(url);
+
+    // A new client, as on the next run
+    for _ in 0..2 {
+        let client = client(HttpCache::new(&dir, Duration::days(1)));
+        assert_eq!(source.fetch_with(&client).await.unwrap(), "download 1");
+    }
+    assert_eq!(conditional.try_iter().collect::<Vec<_>>(), [false, true]);
+
+    // An expired entry is downloaded again
+    let client = client(HttpCache::new(&dir, Duration::zero()));
+    assert_eq!(source.fetch_with(&client).await.unwrap(), "download 2");
+    assert!(!conditional.recv().unwrap());
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn unwritable_cache() {
+    let (url, _conditional) = serve(1);
+    // A file where the cache directory should be
+    let dir = std::env::temp_dir().join(format!("http-cache-file-test-{}", std::process::id()));
+    std::fs::write(&dir, "").unwrap();
+
+    let client = client(HttpCache::new(&dir, Duration::days(1)));
+    assert_eq!(ReleaseNotesSource::Url(url).fetch_with(&client).await.unwrap(), "download 1");
+
+    std::fs::remove_file(dir).unwrap();
+}
@@ -0,0 +1,56 @@
+use rust_changelogs::msrv::MatchKind;
+use rust_changelogs::{Config, MsrvCalculator, VersionManager};
+use semver::Version;
+
+const RELEASES: &str = include_str!("fixtures/RELEASES.md");
+
+#[test]
+fn msrv() {
+    let catalog = VersionManager::new(Config::new()).parse_changelogs(RELEASES).unwrap().catalog;
+    let calculator = MsrvCalculator::new(&catalog);
+
+    let report = calculator.calculate(&[
+        "std::os::unix::fs::chroot",
+        "`core::iter::repeat_n`",
+        "const mem::transmute",
+        "patterns binding in @ bindings",
+        "Option::does_not_exist",
+    ]);
+    let found: Vec<_> = report
+        .requirements
+        .iter()
+        .map(|r| (r.query.as_str(), r.version.to_string(), r.kind, r.matched.as_str()))
+        .collect();
+    assert_eq!(
+        found,
+        [
+            ("std::os::unix::fs::chroot", "1.56.0".to_string(), MatchKind::Api, "std::os::unix::fs::chroot"),
+            ("core::iter::repeat_n", "1.85.0".to_string(), MatchKind::Api, "std::iter::repeat_n"),
+            ("const mem::transmute", "1.56.0".to_string(), MatchKind::ConstApi, "mem::transmute"),
+            (
+                "patterns binding in @ bindings",
+                "1.56.0".to_string(),
+                MatchKind::Language,
+                "Allow specifying a patterns binding in @ bindings.",
+            ),
+        ]
+    );
+    assert_eq!(report.unknown, ["Option::does_not_exist"]);
+    assert_eq!(report.msrv(), Some(&Version::new(1, 85, 0)));
+    assert_eq!(report.rust_version().as_deref(), Some("1.85"));
+
+    // Const stability is a separate requirement
+    assert!(calculator.requirement("mem::transmute").is_none());
+    assert_eq!(calculator.requirement("fs::chroot").map(|r| r.version), Some(Version::new(1, 56, 0)));
+
+    // Paths match on whole segments and need a qualifier, language items on whole words
+    assert_eq!(calculator.requirement("BuildHasherDefault::new").map(|r| r.version), Some(Version::new(1, 85, 0)));
+    assert_eq!(calculator.requirement("new").map(|r| r.kind), Some(MatchKind::Language));
+    assert!(calculator.requirement("fn_addr_eq").is_none());
+    assert!(calculator.requirement("s::chroot").is_none());
+    assert!(calculator.requirement("pattern").is_none());
+    // Ambiguous queries are unknown
+    let report = calculator.calculate(&["Edition is now stable", "stabilize"]);
+    assert!(report.requirements.is_empty());
+    assert_eq!(report.unknown, ["Edition is now stable", "stabilize"]);
+}
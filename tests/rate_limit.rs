@@ -0,0 +1,95 @@
+use chrono::{TimeZone, Utc};
+use http::{HeaderMap, HeaderValue, StatusCode};
+use rust_changelogs::cassette::Exchange;
+use rust_changelogs::rate_limit::RateLimiter;
+use rust_changelogs::{Cassette, Config, GitHubApi, GitHubClient};
+use std::time::Duration;
+
+fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+    pairs
+        .iter()
+        .map(|(name, value)| (name.parse().unwrap(), HeaderValue::from_str(value).unwrap()))
+        .collect()
+}
+
+#[test]
+fn quota_tracking() {
+    let now = Utc.with_ymd_and_hms(2024, 8, 1, 12, 0, 0).unwrap();
+    let limiter = RateLimiter::new();
+    assert_eq!(RateLimiter::resource("/search/issues"), "search");
+    assert_eq!(RateLimiter::resource("/repos/rust-lang/rust/issues"), "core");
+
+    let reset = (now.timestamp() + 30).to_string();
+    limiter.update("search", &headers(&[("x-ratelimit-limit", "30"), ("x-ratelimit-remaining", "1"), ("x-ratelimit-reset", &reset)]));
+    assert_eq!(limiter.delay_before("search", now), None);
+
+    // A request in flight takes the last one
+    limiter.reserve("search");
+    assert_eq!(limiter.delay_before("search", now), Some(Duration::from_secs(31)));
+
+    limiter.update("search", &headers(&[("x-ratelimit-remaining", "0"), ("x-ratelimit-resource", "search")]));
+    assert_eq!(limiter.delay_before("search", now), Some(Duration::from_secs(31)));
+    // The buckets are independent
+    assert_eq!(limiter.delay_before("core", now), None);
+    assert_eq!(limiter.bucket("search").unwrap().requests, 2);
+    assert_eq!(limiter.report(), "search: 2 requests, 0/30 left, resets at 12:00:30 UTC\n");
+}
+
+#[test]
+fn retries() {
+    let now = Utc.with_ymd_and_hms(2024, 8, 1, 12, 0, 0).unwrap();
+    let limiter = RateLimiter::new();
+    let none = HeaderMap::new();
+
+    let retry_after = headers(&[("retry-after", "7")]);
+    assert_eq!(limiter.retry_delay("core", StatusCode::FORBIDDEN, &retry_after, b"", 0, now), Some(Duration::from_secs(7)));
+    assert_eq!(limiter.retry_delay("core", StatusCode::FORBIDDEN, &retry_after, b"", 4, now), None);
+
+    let secondary = br#"{"message": "You have exceeded a secondary rate limit."}"#;
+    assert_eq!(limiter.retry_delay("core", StatusCode::FORBIDDEN, &none, secondary, 1, now), Some(Duration::from_secs(120)));
+    assert_eq!(limiter.retry_delay("core", StatusCode::FORBIDDEN, &none, b"Resource not accessible", 0, now), None);
+    assert_eq!(limiter.retry_delay("core", StatusCode::OK, &retry_after, b"", 0, now), None);
+
+    // Transient server errors back off exponentially
+    assert_eq!(limiter.retry_delay("core", StatusCode::BAD_GATEWAY, &none, b"", 0, now), Some(Duration::from_secs(2)));
+    assert_eq!(limiter.retry_delay("core", StatusCode::SERVICE_UNAVAILABLE, &none, b"", 2, now), Some(Duration::from_secs(8)));
+    assert_eq!(limiter.retry_delay("core", StatusCode::GATEWAY_TIMEOUT, &none, b"", 4, now), None);
+    assert_eq!(limiter.retry_delay("core", StatusCode::INTERNAL_SERVER_ERROR, &none, b"", 0, now), None);
+}
+
+#[tokio::test]
+async fn rate_limited_response_is_retried() {
+    let url = "https://api.github.com/repos/rust-lang/rust/issues?state=closed&milestone=111&labels=relnotes&sort=created&direction=asc&per_page=255";
+    let cassette = Cassette::replaying(
+        [
+            (429, vec![("retry-after".to_string(), "0".to_string())], "{}".to_string()),
+            (200, vec![("x-ratelimit-remaining".to_string(), "4999".to_string())], "[]".to_string()),
+        ]
+        .into_iter()
+        .map(|(status, headers, body)| Exchange { method: "GET".to_string(), url: url.to_string(), status, headers, body }),
+    );
+
+    let github = GitHubClient::new(Config { cassette: Some(cassette), ..Config::new() });
+    assert!(github.fetch_milestone_issues(111).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn concurrent_searches_skip_failed_terms() {
+    let search = |term: &str| {
+        format!("https://api.github.com/search/issues?q=is%3Apr+is%3Aopen+in%3Atitle+repo%3Arust-lang%2Frust+{term}&sort=created_at&order=desc")
+    };
+    let found = r#"{"total_count": 0, "incomplete_results": false, "items": []}"#;
+    let cassette = Cassette::replaying([
+        Exchange { method: "GET".to_string(), url: search("stabilize"), status: 200, headers: Vec::new(), body: found.to_string() },
+        Exchange { method: "GET".to_string(), url: search("stabilise"), status: 422, headers: Vec::new(), body: "{}".to_string() },
+    ]);
+
+    let config = Config {
+        cassette: Some(cassette),
+        github_search_concurrency: 2,
+        stabilization_search_terms: vec!["stabilise", "stabilize"],
+        ..Config::new()
+    };
+    let github = GitHubClient::new(config);
+    assert!(github.fetch_stabilization_prs().await.unwrap().is_empty());
+}
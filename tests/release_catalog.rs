@@ -0,0 +1,63 @@
+use chrono::NaiveDate;
+use rust_changelogs::release_catalog::Channel;
+use rust_changelogs::{Config, ReleaseCatalog, VersionManager};
+use semver::Version;
+
+const RELEASES: &str = include_str!("fixtures/RELEASES.md");
+
+fn catalog() -> ReleaseCatalog {
+    VersionManager::new(Config::new()).parse_changelogs(RELEASES).unwrap().catalog
+}
+
+fn v(version: &str) -> Version {
+    Version::parse(version).unwrap()
+}
+
+fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(y, m, d).unwrap()
+}
+
+#[test]
+fn navigation() {
+    let catalog = catalog();
+
+    assert_eq!(catalog.versions().next(), Some(&v("0.12.0")));
+    assert_eq!(catalog.previous(&v("1.80.0")).map(|e| &e.version), Some(&v("1.56.0")));
+    assert_eq!(catalog.next(&v("1.80.0")).map(|e| &e.version), Some(&v("1.80.1")));
+    assert_eq!(catalog.next(&v("1.85.0")).map(|e| &e.version), None);
+    assert_eq!(catalog.previous(&v("1.0.0")).map(|e| &e.version), Some(&v("1.0.0-alpha.2")));
+
+    let patches: Vec<_> = catalog.patch_releases(1, 80).map(|e| &e.version).collect();
+    assert_eq!(patches, [&v("1.80.1")]);
+    assert_eq!(catalog.minor_series(1, 80).count(), 2);
+}
+
+#[test]
+fn channels() {
+    let catalog = catalog();
+    let as_of = date(2024, 8, 1);
+
+    assert_eq!(catalog.latest_stable(as_of).map(|e| &e.version), Some(&v("1.80.0")));
+    assert_eq!(catalog.latest_stable(date(2015, 3, 1)).map(|e| &e.version), Some(&v("0.12.0")));
+    assert_eq!(catalog.current_versions(as_of), Some((v("1.80.0"), v("1.81.0"), v("1.82.0"))));
+
+    assert_eq!(catalog.channel(&v("1.56.0"), as_of), Channel::Released);
+    assert_eq!(catalog.channel(&v("1.80.1"), as_of), Channel::Future);
+    assert_eq!(catalog.channel(&v("1.81.0"), as_of), Channel::Beta);
+    assert_eq!(catalog.channel(&v("1.82.0"), as_of), Channel::Nightly);
+    assert_eq!(catalog.channel(&v("1.85.0"), as_of), Channel::Future);
+    assert_eq!(catalog.channel(&v("1.85.0"), date(2025, 2, 20)), Channel::Released);
+}
+
+#[test]
+fn historical_catalog() {
+    let catalog = catalog().as_of(date(2024, 8, 1));
+
+    assert!(catalog.contains(&v("1.80.0")));
+    assert!(!catalog.contains(&v("1.80.1")));
+    assert!(!catalog.contains(&v("1.85.0")));
+    assert_eq!(catalog.versions().next_back(), Some(&v("1.80.0")));
+    assert_eq!(catalog.current_versions(date(2024, 8, 1)), Some((v("1.80.0"), v("1.81.0"), v("1.82.0"))));
+    // Nothing released later shows through, even when asking about a later date
+    assert_eq!(catalog.current_versions(date(2025, 3, 1)), Some((v("1.80.0"), v("1.81.0"), v("1.82.0"))));
+}
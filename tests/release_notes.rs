@@ -0,0 +1,149 @@
+use rust_changelogs::release_notes::{ApiKind, Block, GitHubReference, ReferenceKind, SectionKind};
+use rust_changelogs::{Config, ReleaseNotes, ReleaseNotesSource, VersionManager};
+use semver::Version;
+
+const RELEASES: &str = include_str!("fixtures/RELEASES.md");
+
+#[test]
+fn parses_sections_items_and_links() {
+    let version_manager = VersionManager::new(Config::new());
+    let catalog = version_manager.parse_changelogs(RELEASES).unwrap().catalog;
+
+    let notes = &catalog[&Version::parse("1.80.0").unwrap()].notes;
+    let kinds: Vec<_> = notes.sections.iter().map(|s| s.kind).collect();
+    assert_eq!(
+        kinds,
+        [
+            SectionKind::Language,
+            SectionKind::Libraries,
+            SectionKind::StabilizedApis,
+            SectionKind::Cargo,
+            SectionKind::CompatibilityNotes,
+            SectionKind::InternalChanges,
+        ]
+    );
+    assert_eq!(notes.section(SectionKind::StabilizedApis).unwrap().items().count(), 5);
+    assert_eq!(notes.link("#126018"), Some("https://github.com/rust-lang/rust/issues/126018"));
+
+    let internal = notes.section(SectionKind::InternalChanges).unwrap();
+    assert!(matches!(internal.blocks[0], Block::Text(_)));
+    assert_eq!(internal.items().count(), 1);
+
+    let notes = &catalog[&Version::parse("1.56.0").unwrap()].notes;
+    let language = notes.section(SectionKind::Language).unwrap();
+    let edition = language.items().next().unwrap();
+    assert_eq!(
+        edition.text,
+        "[The 2021 Edition is now stable.][rust#88100]\n  See [the edition guide][rust-2021-edition-guide] for more details."
+    );
+    assert_eq!(notes.section(SectionKind::StabilizedApis).unwrap().items().count(), 3);
+
+    let notes = &catalog[&Version::parse("1.80.1").unwrap()].notes;
+    assert_eq!(notes.sections.len(), 1);
+    assert_eq!(notes.sections[0].title, None);
+    assert_eq!(notes.sections[0].items().count(), 2);
+}
+
+#[test]
+fn markdown_round_trip() {
+    let body = "Language\n--------\n- First\n  continued\n- Second\n\nLibraries\n---------\n\nSome text.\n\n- [Item][1]\n\n[1]: https://example.com\n";
+    let notes = ReleaseNotes::parse(body);
+
+    assert_eq!(
+        notes.to_markdown(),
+        "Language\n--------\n- First\n  continued\n- Second\n\nLibraries\n---------\nSome text.\n\n- [Item][1]\n\n[1]: https://example.com\n"
+    );
+    assert_eq!(ReleaseNotes::parse(&notes.to_markdown()), notes);
+}
+
+#[test]
+fn code_blocks_round_trip() {
+    let body = "Language\n--------\n- Item\n\n```rust\n# fn main() {}\n- x\n\n[y]: https://example.com\n```\n\n    indented code\n      more\n\n- Second";
+    let notes = ReleaseNotes::parse(body);
+
+    assert_eq!(notes.sections.len(), 1);
+    assert_eq!(notes.sections[0].items().count(), 2);
+    assert!(notes.links.is_empty());
+    assert_eq!(notes.to_markdown(), body);
+    assert_eq!(ReleaseNotes::parse(&notes.to_markdown()), notes);
+
+    // A fence inside an item stays part of it
+    let body = "- Item\n  ~~~\n  # not a heading\n\n  ~~~\n- Next";
+    let notes = ReleaseNotes::parse(body);
+    assert_eq!(notes.sections[0].items().next().unwrap().text, "Item\n  ~~~\n  # not a heading\n\n  ~~~");
+    assert_eq!(notes.to_markdown(), body);
+}
+
+#[test]
+fn resolves_github_references() {
+    let version_manager = VersionManager::new(Config::new());
+    let parsed = version_manager.parse_changelogs(RELEASES).unwrap();
+
+    let notes = &parsed.catalog[&Version::parse("1.56.0").unwrap()].notes;
+    let edition = notes.items().next().unwrap();
+    assert_eq!(
+        edition.references,
+        [GitHubReference {
+            owner: "rust-lang".to_string(),
+            repo: "rust".to_string(),
+            number: 88100,
+            kind: ReferenceKind::PullRequest,
+        }]
+    );
+
+    let notes = &parsed.catalog[&Version::parse("1.80.0").unwrap()].notes;
+    let box_pointers = notes.section(SectionKind::CompatibilityNotes).unwrap().items().last().unwrap();
+    assert_eq!(box_pointers.references[0].kind, ReferenceKind::Issue);
+
+    let index = parsed.catalog.reference_index();
+    assert_eq!(index.lookup("rust-lang", "rust", 128271), Some(&Version::parse("1.80.1").unwrap()));
+    assert_eq!(index.lookup("rust-lang", "cargo", 9732), Some(&Version::parse("1.56.0").unwrap()));
+    assert_eq!(index.lookup("rust-lang", "rust", 9732), None);
+}
+
+#[test]
+fn stabilized_apis() {
+    let version_manager = VersionManager::new(Config::new());
+    let catalog = version_manager.parse_changelogs(RELEASES).unwrap().catalog;
+
+    let notes = &catalog[&Version::parse("1.85.0").unwrap()].notes;
+    let apis = notes.stabilized_apis();
+    let summary: Vec<_> = apis.iter().map(|api| (api.path.as_str(), api.kind, api.is_const)).collect();
+    assert_eq!(
+        summary,
+        [
+            ("BuildHasherDefault::new", ApiKind::Method, false),
+            ("ptr::fn_addr_eq", ApiKind::Function, false),
+            ("io::ErrorKind::QuotaExceeded", ApiKind::Variant, false),
+            ("impl Extend<(A, B)> for (A, B)", ApiKind::TraitImpl, false),
+            ("std::iter::repeat_n", ApiKind::Function, false),
+            ("mem::size_of_val", ApiKind::Function, true),
+            ("<f32>::abs", ApiKind::Method, true),
+        ]
+    );
+
+    let notes = &catalog[&Version::parse("1.56.0").unwrap()].notes;
+    let apis = notes.stabilized_apis();
+    assert_eq!(apis.len(), 3);
+    assert_eq!(
+        apis[0].docs_url.as_deref(),
+        Some("https://doc.rust-lang.org/stable/std/os/unix/fs/fn.chroot.html")
+    );
+    assert_eq!((apis[2].path.as_str(), apis[2].is_const), ("mem::transmute", true));
+}
+
+#[tokio::test]
+async fn release_notes_from_file() {
+    let source = ReleaseNotesSource::File(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/RELEASES.md").into());
+    assert_eq!(source.fetch().await.unwrap(), RELEASES);
+
+    let missing = ReleaseNotesSource::File("does/not/exist.md".into());
+    assert!(missing.fetch().await.is_err());
+
+    let dir = std::env::temp_dir().join(format!("release-notes-dir-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("RELEASES.md"), RELEASES).unwrap();
+    let result = ReleaseNotesSource::Directory(dir.clone()).fetch().await;
+    std::fs::remove_dir_all(dir).unwrap();
+    assert_eq!(result.unwrap(), RELEASES);
+}
@@ -1,22 +1,27 @@
 
+use chrono::NaiveDate;
 use semver::Version;
+use rust_changelogs::release_schedule::{CycleEvent, EventAnchor, ScheduleOverride};
 use rust_changelogs::{Config, VersionManager};
 use itertools::Itertools;
+use quickcheck::{quickcheck, Arbitrary, Gen};
 
 #[test]
 fn version_weights() {
     let config = Config::new();
-    let version_manager = VersionManager::new(config);
+    let mut version_manager = VersionManager::new(config);
 
-    let versions = vec![
+    let versions = [
         Version::parse("1.90.0").unwrap(),
         Version::parse("1.85.1").unwrap(),
         Version::parse("1.1.0").unwrap(),
         Version::parse("1.0.0").unwrap(),
+        Version::parse("1.0.0-alpha.10").unwrap(),
         Version::parse("1.0.0-alpha.2").unwrap(),
         Version::parse("1.0.0-alpha").unwrap(),
         Version::parse("0.12.0").unwrap(),
     ];
+    version_manager.register_versions(&versions);
 
     let weights: Vec<_> = versions.iter()
         .map(|v| (v, version_manager.determine_weight(v)))
@@ -28,3 +33,276 @@ fn version_weights() {
         assert_eq!(version, &versions[index]);
     }
 }
+
+#[derive(Debug, Clone)]
+struct ArbitraryVersion(Version);
+
+impl Arbitrary for ArbitraryVersion {
+    fn arbitrary(g: &mut Gen) -> Self {
+        // Mostly small components so that versions share prefixes, sometimes huge ones
+        let component = |g: &mut Gen| {
+            if bool::arbitrary(g) {
+                u64::arbitrary(g) % 4
+            } else {
+                u64::arbitrary(g)
+            }
+        };
+        let identifier = |g: &mut Gen| match u8::arbitrary(g) % 4 {
+            0 => "alpha".to_string(),
+            1 => "beta".to_string(),
+            2 => (u64::arbitrary(g) % 12).to_string(),
+            _ => u64::arbitrary(g).to_string(),
+        };
+
+        let mut version = Version::new(component(g), component(g), component(g));
+        let pre_len = usize::arbitrary(g) % 4;
+        if pre_len > 0 {
+            let pre = (0..pre_len).map(|_| identifier(g)).join(".");
+            version.pre = semver::Prerelease::new(&pre).unwrap();
+        }
+
+        Self(version)
+    }
+}
+
+quickcheck! {
+    fn weights_follow_semver_precedence(versions: Vec<ArbitraryVersion>) -> bool {
+        let mut version_manager = VersionManager::new(Config::new());
+        version_manager.register_versions(versions.iter().map(|v| &v.0));
+
+        versions.iter().map(|v| &v.0).tuple_combinations().all(|(a, b)| {
+            let (weight_a, weight_b) = (version_manager.determine_weight(a), version_manager.determine_weight(b));
+            // Newer versions come first in the sidebar, i.e. get lower weights
+            a.cmp(b) == weight_b.cmp(&weight_a)
+        })
+    }
+
+    fn unregistered_version_is_ordered(versions: Vec<ArbitraryVersion>, extra: ArbitraryVersion) -> bool {
+        let mut version_manager = VersionManager::new(Config::new());
+        version_manager.register_versions(versions.iter().map(|v| &v.0).filter(|v| **v != extra.0));
+
+        let extra_weight = version_manager.determine_weight(&extra.0);
+        versions.iter().map(|v| &v.0).filter(|v| **v != extra.0).all(|v| {
+            v.cmp(&extra.0) == extra_weight.cmp(&version_manager.determine_weight(v))
+        })
+    }
+}
+
+#[test]
+fn malformed_sections_get_diagnostics() {
+    let version_manager = VersionManager::new(Config::new());
+    let body = "Version 1.81.0 (TBD)
+==========================
+
+- Not released yet
+
+Version 1.80.x (2024-07-25)
+==========================
+
+- Bad version
+
+Version 1.80.0 (2024-07-25)
+==========================
+
+- Good
+";
+
+    let parsed = version_manager.parse_changelogs(body).unwrap();
+
+    // The undated section is unreleased, the malformed one is skipped
+    assert_eq!(parsed.catalog.versions().collect::<Vec<_>>(), [&Version::new(1, 80, 0), &Version::new(1, 81, 0)]);
+    assert_eq!(parsed.catalog[&Version::new(1, 81, 0)].release_date, None);
+    assert_eq!(
+        parsed.diagnostics.iter().map(|d| (d.line, d.reason.as_str())).collect::<Vec<_>>(),
+        [
+            (1, "no release date in '(TBD)', read as unreleased"),
+            (6, "unparsable version '1.80.x', section skipped")
+        ]
+    );
+    assert!(version_manager.parse_changelogs("<html>404</html>").is_err());
+}
+
+#[test]
+fn version_heading_variants() {
+    let version_manager = VersionManager::new(Config::new());
+    let body = "# Version 1.82.0
+
+- Unreleased
+
+Version 1.81.0 (2024-09-05)
+
+==========================
+
+- Extra blank line
+
+## Version 1.0.0-alpha.2 (2015-02-20) ##
+
+- ATX pre-release
+
+Version 2 of the resolver is now the default.
+
+```text
+Version 1.0.0 (2015-05-15)
+==========================
+```
+
+Version 1.2 (2015-08-07)
+====================
+
+- No patch number
+";
+
+    let parsed = version_manager.parse_changelogs(body).unwrap();
+    assert!(parsed.diagnostics.is_empty());
+
+    let date = |version: &str| parsed.catalog[&Version::parse(version).unwrap()].release_date;
+    assert_eq!(date("1.82.0"), None);
+    assert_eq!(date("1.81.0"), NaiveDate::from_ymd_opt(2024, 9, 5));
+    assert_eq!(date("1.0.0-alpha.2"), NaiveDate::from_ymd_opt(2015, 2, 20));
+    assert_eq!(date("1.2.0"), NaiveDate::from_ymd_opt(2015, 8, 7));
+
+    // Neither the paragraph nor the fenced heading start a section
+    assert!(parsed.catalog.get(&Version::new(1, 0, 0)).is_none());
+    let alpha = &parsed.catalog[&Version::parse("1.0.0-alpha.2").unwrap()].notes;
+    assert!(alpha.to_markdown().contains("Version 2 of the resolver is now the default."));
+
+    for entry in parsed.catalog.iter() {
+        assert_eq!(entry.notes.sections.len(), 1);
+        assert_eq!(entry.notes.items().count(), 1);
+    }
+}
+
+#[test]
+fn release_schedule() {
+    let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+    let mut config = Config::new();
+    config.schedule_overrides.push(ScheduleOverride {
+        version: Version::new(1, 87, 0),
+        release_date: Some(date(2025, 5, 16)),
+        branch_date: None,
+    });
+    let mut version_manager = VersionManager::new(config);
+
+    // Cadence only
+    let dates = version_manager.dates_for_version(&Version::new(1, 85, 0));
+    assert_eq!(dates.release_date, date(2025, 2, 20));
+    assert_eq!(dates.branch_date, date(2025, 1, 3));
+
+    // 1.85.0 is known to have shipped a week late, later versions are projected from it
+    let body = "Version 1.85.0 (2025-02-27)\n==========================\n\n- Late\n";
+    version_manager.set_known_releases(&version_manager.parse_changelogs(body).unwrap().catalog);
+
+    assert_eq!(version_manager.dates_for_version(&Version::new(1, 85, 0)).release_date, date(2025, 2, 27));
+    assert_eq!(version_manager.dates_for_version(&Version::new(1, 86, 0)).release_date, date(2025, 4, 10));
+    assert_eq!(version_manager.dates_for_version(&Version::new(1, 86, 0)).branch_date, date(2025, 2, 21));
+
+    let overridden = version_manager.dates_for_version(&Version::new(1, 87, 0));
+    assert_eq!(overridden.release_date, date(2025, 5, 16));
+    assert_eq!(overridden.branch_date, date(2025, 4, 4));
+    // Overrides don't shift the versions after them
+    assert_eq!(version_manager.dates_for_version(&Version::new(1, 88, 0)).release_date, date(2025, 7, 3));
+
+    // Relative to the stable version of a date, with the same slipped and overridden dates
+    assert_eq!(version_manager.calculate_release_date(date(2025, 3, 1), 1).unwrap().release_date, date(2025, 4, 10));
+    assert_eq!(version_manager.calculate_release_date(date(2025, 3, 1), 2), Some(overridden));
+}
+
+#[test]
+fn version_date_lookup() {
+    let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+    let v = |version: &str| Version::parse(version).unwrap();
+    let mut version_manager = VersionManager::new(Config::new());
+    let releases = include_str!("fixtures/RELEASES.md");
+    version_manager.set_known_releases(&version_manager.parse_changelogs(releases).unwrap().catalog);
+
+    // Past, with a point release
+    assert_eq!(version_manager.version_for_date(date(2024, 8, 20)).unwrap(), (v("1.80.1"), v("1.81.0"), v("1.82.0")));
+    assert_eq!(version_manager.version_for_date(date(2024, 8, 1)).unwrap(), (v("1.80.0"), v("1.81.0"), v("1.82.0")));
+    // Release day
+    assert_eq!(version_manager.version_for_date(date(2025, 2, 20)).unwrap().0, v("1.85.0"));
+    assert_eq!(version_manager.version_for_date(date(2025, 2, 19)).unwrap().0, v("1.84.0"));
+    // Projected
+    assert_eq!(version_manager.version_for_date(date(2026, 10, 17)).unwrap(), (v("1.99.0"), v("1.100.0"), v("1.101.0")));
+    assert_eq!(version_manager.dates_for_version(&v("1.95.0")).release_date, date(2026, 4, 16));
+
+    // A point release has its own date, but the branch date of its series
+    let point_release = version_manager.dates_for_version(&v("1.80.1"));
+    assert_eq!(point_release.release_date, date(2024, 8, 8));
+    assert_eq!(point_release.branch_date, version_manager.dates_for_version(&v("1.80.0")).branch_date);
+    // Nothing was released before 1.0
+    assert_eq!(version_manager.version_for_date(date(2014, 1, 1)), None);
+    assert_eq!(version_manager.version_for_date(date(2015, 5, 15)).unwrap().0, v("1.0.0"));
+}
+
+#[test]
+fn cycle_events() {
+    let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+    let mut config = Config::new();
+    config.cycle_events = vec![
+        CycleEvent::new("Release blog post", EventAnchor::Release, 0),
+        CycleEvent::new("Triage meeting", EventAnchor::Branch, 1),
+    ];
+    config.schedule_overrides.push(ScheduleOverride {
+        version: Version::new(1, 87, 0),
+        release_date: Some(date(2025, 5, 16)),
+        branch_date: None,
+    });
+    let version_manager = VersionManager::new(config);
+
+    let events: Vec<_> = version_manager
+        .dates_for_version(&Version::new(1, 87, 0))
+        .events
+        .into_iter()
+        .map(|event| (event.name, event.date))
+        .collect();
+    assert_eq!(
+        events,
+        [
+            ("Triage meeting".to_string(), date(2025, 3, 29)),
+            ("Release blog post".to_string(), date(2025, 5, 16)),
+        ]
+    );
+}
+
+#[test]
+fn schedule_override_list() {
+    let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+    let overrides =
+        ScheduleOverride::parse_list("# shifted releases\n1.87 release=2025-05-16; 1.88.0 branch=2025-05-09\n").unwrap();
+    assert_eq!(
+        overrides,
+        [
+            ScheduleOverride { version: Version::new(1, 87, 0), release_date: Some(date(2025, 5, 16)), branch_date: None },
+            ScheduleOverride { version: Version::new(1, 88, 0), release_date: None, branch_date: Some(date(2025, 5, 9)) },
+        ]
+    );
+
+    assert!(ScheduleOverride::parse_list("1.87 released=2025-05-16").is_err());
+    assert!(ScheduleOverride::parse_list("1.87 release=May").is_err());
+}
+
+#[test]
+fn cycle_event_list() {
+    let events = CycleEvent::parse_list(
+        "# every cycle\nbranch+6 Promoted to beta; release-7..-1 Beta backport freeze\nrelease Release blog post\n",
+    )
+    .unwrap();
+    assert_eq!(
+        events,
+        [
+            CycleEvent::new("Promoted to beta", EventAnchor::Branch, 6),
+            CycleEvent::new("Beta backport freeze", EventAnchor::Release, -7).until(-1),
+            CycleEvent::new("Release blog post", EventAnchor::Release, 0),
+        ]
+    );
+
+    let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+    let freeze = &events[1];
+    assert_eq!(freeze.date(date(2024, 9, 5), date(2024, 7, 19)), date(2024, 8, 29));
+    assert_eq!(freeze.end_date(date(2024, 9, 5), date(2024, 7, 19)), Some(date(2024, 9, 4)));
+
+    assert!(CycleEvent::parse_list("merge+1 Triage").is_err());
+    assert!(CycleEvent::parse_list("release-2").is_err());
+    assert!(CycleEvent::parse_list("release-1..-7 Backwards").is_err());
+    assert!(CycleEvent::parse_list("release-x Typo").is_err());
+}